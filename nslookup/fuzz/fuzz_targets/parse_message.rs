@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use nslookup::response::{Message, Response};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(message) = Message::parse(data) {
+        let _ = message.header.to_string();
+    }
+    if let Ok(response) = Response::parse_response(data) {
+        let _ = response.to_string();
+    }
+});
@@ -0,0 +1,422 @@
+use crate::client::{frame, random_id, server_order, Action, Pending};
+use crate::config::ResolverConfig;
+use crate::cookie::CookieJar;
+use crate::customerror::CustomError;
+use crate::header::Header;
+use crate::qtype::Qtype;
+use crate::question::{DnsMessageBuilder, Question};
+use crate::response::Response;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use std::vec::Vec;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::time::{self, Instant};
+
+/// Asks the name servers for a single type of record of a name and returns the Response.
+/// The name is asked as it is, the search list of the config isn't used.
+///
+/// Every future of this module can be cancelled by dropping it, e.g. with `tokio::time::timeout`
+/// or `tokio::select!`. The sockets are closed then and late replies are ignored.
+///
+/// # Arguments
+///
+/// * `name` - The name, e.g. example.com
+/// * `qtype` - The type of the records
+/// * `config` - The name servers to ask
+pub async fn query(
+    name: &str,
+    qtype: Qtype,
+    config: &ResolverConfig,
+) -> Result<Response, CustomError> {
+    ask(vec![Question::new(name, qtype)?], config).await
+}
+
+/// Looks up a host like nslookup does and returns the Response.
+/// An ip is looked up with a PTR question, a host name with an A and an AAAA question
+/// for every name of the search list until one of them has records (see config::SearchList).
+/// If the names only exist without records, the Response of the first one is returned, see Response::is_nodata.
+///
+/// # Arguments
+///
+/// * `host` - The host name or ip
+/// * `config` - The name servers to ask
+pub async fn lookup(host: &str, config: &ResolverConfig) -> Result<Response, CustomError> {
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return ask(vec![Question::reverse(&ip)], config).await;
+    }
+
+    let mut search = config.search_list(host, Response::is_nodata);
+    while let Some(name) = search.next_name() {
+        let questions = vec![
+            Question::new(&name, Qtype::A)?,
+            Question::new(&name, Qtype::AAAA)?,
+        ];
+        if let Some(result) = search.record(ask(questions, config).await) {
+            return result;
+        }
+    }
+    search.finish()
+}
+
+/// Returns the IPv4 and IPv6 adresses of a host.
+/// An ip is returned as it is without asking a name server.
+///
+/// # Arguments
+///
+/// * `host` - The host name or ip
+/// * `config` - The name servers to ask
+pub async fn lookup_ip(host: &str, config: &ResolverConfig) -> Result<Vec<IpAddr>, CustomError> {
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Ok(vec![ip]);
+    }
+    Ok(lookup(host, config).await?.ips())
+}
+
+/// Sends several DNS-Messages at once and returns the replies in the order of the messages.
+/// Works like client::query_all: the name servers are asked one after another,
+/// a truncated UDP reply is repeated over TCP and every name server gets `timeout` to answer.
+///
+/// # Arguments
+///
+/// * `messages` - The DNS-Messages, e.g. the A and AAAA question of a name
+/// * `config` - The name servers to ask
+/// * `cookies` - The cookies of the name servers, None to send no cookies
+pub async fn query_all(
+    messages: &[Vec<u8>],
+    config: &ResolverConfig,
+    mut cookies: Option<&mut CookieJar>,
+) -> Result<Vec<Vec<u8>>, CustomError> {
+    let mut replies = vec![None; messages.len()];
+    let mut last_error = CustomError::NoNameserver;
+    for server in server_order(config)? {
+        match send_all(
+            messages,
+            &mut replies,
+            &server,
+            config,
+            cookies.as_deref_mut(),
+        )
+        .await
+        {
+            Ok(()) => return Ok(replies.into_iter().flatten().collect()),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+/// Sends the questions at once and combines the replies into one Response
+///
+/// # Arguments
+///
+/// * `questions` - The questions of a name
+/// * `config` - The name servers to ask
+async fn ask(questions: Vec<Question>, config: &ResolverConfig) -> Result<Response, CustomError> {
+    let header = Header::new(random_id(), false, 0);
+    let messages = DnsMessageBuilder::new(header, questions).build_messages()?;
+    let replies = query_all(&messages, config, None).await?;
+    Response::from_replies(&replies)
+}
+
+/// Sends all messages without a reply to a single name server and stores the replies.
+/// Uses TCP if the config says so or a UDP reply has the truncated flag set.
+/// Returns an error if a message isn't answered, the replies received so far are kept.
+///
+/// # Arguments
+///
+/// * `messages` - The DNS-Messages
+/// * `replies` - The replies of the messages, None if there is none yet
+/// * `server` - The socket adress of the name server
+/// * `config` - The config containing the transport and the timeout
+/// * `cookies` - The cookies of the name servers, None to send no cookies
+async fn send_all(
+    messages: &[Vec<u8>],
+    replies: &mut [Option<Vec<u8>>],
+    server: &SocketAddr,
+    config: &ResolverConfig,
+    cookies: Option<&mut CookieJar>,
+) -> Result<(), CustomError> {
+    let mut pending = Pending::new(messages, replies, *server, cookies, config.tcp);
+    if config.tcp {
+        for index in pending.unanswered() {
+            let query = pending.send(index)?;
+            let reply = send_tcp(&query, server, config).await?;
+            on_tcp_reply(&mut pending, reply, server, config).await?;
+        }
+        return Ok(());
+    }
+
+    let bind = if server.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let sock = UdpSocket::bind(bind).await?;
+    for index in pending.unanswered() {
+        sock.send_to(&pending.send(index)?, server).await?;
+    }
+
+    let mut buf = vec![0u8; usize::from(u16::MAX)];
+    // Replies that don't belong to a query are dropped, the timeout applies to the whole wait
+    let deadline = config.timeout.map(|timeout| Instant::now() + timeout);
+    while !pending.is_done() {
+        let (amt, src) = match deadline {
+            Some(deadline) => time::timeout_at(deadline, sock.recv_from(&mut buf))
+                .await
+                .map_err(|_| CustomError::Timeout(*server))??,
+            None => sock.recv_from(&mut buf).await?,
+        };
+        match pending.on_reply(buf[..amt].to_vec(), src)? {
+            Action::Tcp(query) => {
+                let reply = send_tcp(&query, server, config).await?;
+                if let Some(query) = on_tcp_reply(&mut pending, reply, server, config).await? {
+                    sock.send_to(&query, server).await?;
+                }
+            }
+            Action::Resend(query) => {
+                sock.send_to(&query, server).await?;
+            }
+            Action::Ignore | Action::Done => {}
+        }
+    }
+    Ok(())
+}
+
+/// Handles a reply that came over TCP like client::on_tcp_reply does
+///
+/// # Arguments
+///
+/// * `pending` - The messages that wait for their replies
+/// * `reply` - The raw reply
+/// * `server` - The socket adress of the name server
+/// * `config` - The config containing the transport and the timeout
+async fn on_tcp_reply(
+    pending: &mut Pending<'_>,
+    mut reply: Vec<u8>,
+    server: &SocketAddr,
+    config: &ResolverConfig,
+) -> Result<Option<Vec<u8>>, CustomError> {
+    loop {
+        match pending.on_reply(reply, *server)? {
+            Action::Done => return Ok(None),
+            Action::Ignore => return Err(CustomError::ResponseError),
+            Action::Resend(query) if !config.tcp => return Ok(Some(query)),
+            Action::Resend(query) | Action::Tcp(query) => {
+                reply = send_tcp(&query, server, config).await?;
+            }
+        }
+    }
+}
+
+/// Sends a DNS-Message over TCP to a single name server and returns the raw reply.
+/// Both messages are prefixed with their length as two bytes (RFC 1035 4.2.2).
+///
+/// # Arguments
+///
+/// * `message` - The DNS-Message containing Header and Question
+/// * `server` - The socket adress of the name server
+/// * `config` - The config containing the timeout
+async fn send_tcp(
+    message: &[u8],
+    server: &SocketAddr,
+    config: &ResolverConfig,
+) -> Result<Vec<u8>, CustomError> {
+    let framed = frame(message)?;
+    let exchange = async {
+        let mut stream = TcpStream::connect(server).await?;
+        stream.write_all(&framed).await?;
+
+        let mut length = [0u8; 2];
+        stream.read_exact(&mut length).await?;
+        let mut buf = vec![0u8; u16::from_be_bytes(length) as usize];
+        stream.read_exact(&mut buf).await?;
+        Ok(buf)
+    };
+    with_timeout(exchange, server, config.timeout).await
+}
+
+/// Awaits a future for at most the timeout, the server is named if it takes longer
+///
+/// # Arguments
+///
+/// * `future` - The exchange with the name server
+/// * `server` - The socket adress of the name server
+/// * `timeout` - The timeout of the config, None to wait forever
+async fn with_timeout<T, F>(
+    future: F,
+    server: &SocketAddr,
+    timeout: Option<Duration>,
+) -> Result<T, CustomError>
+where
+    F: Future<Output = Result<T, CustomError>>,
+{
+    match timeout {
+        Some(timeout) => time::timeout(timeout, future)
+            .await
+            .map_err(|_| CustomError::Timeout(*server))?,
+        None => future.await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Runs a future on a runtime of its own
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    /// Returns the reply to a query, an A question is answered with 192.0.2.1 and every other question with no record
+    fn answer(query: &[u8]) -> Vec<u8> {
+        let mut reply = query.to_vec();
+        reply[2] |= 0x80;
+        if query[query.len() - 4..query.len() - 2] == [0, 1] {
+            reply[7] = 1;
+            reply.extend_from_slice(&[0xc0, 12, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 192, 0, 2, 1]);
+        }
+        reply
+    }
+
+    /// Answers a number of UDP queries
+    fn serve_udp(server: std::net::UdpSocket, count: usize) {
+        for _ in 0..count {
+            let mut buf = [0u8; 512];
+            let (amt, src) = server.recv_from(&mut buf).unwrap();
+            server.send_to(&answer(&buf[..amt]), src).unwrap();
+        }
+    }
+
+    fn config(addr: SocketAddr) -> ResolverConfig {
+        let mut config = ResolverConfig::new(addr.ip());
+        config.port = addr.port();
+        config.timeout = Some(Duration::from_millis(200));
+        config.attempts = 1;
+        config
+    }
+
+    #[test]
+    fn test_query() {
+        let server = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = thread::spawn(move || serve_udp(server, 1));
+
+        let response = block_on(query("example.com", Qtype::A, &config(addr))).unwrap();
+        assert_eq!(response.ips(), vec!["192.0.2.1".parse::<IpAddr>().unwrap()]);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_lookup_ip() {
+        let server = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = thread::spawn(move || serve_udp(server, 2));
+
+        let ips = block_on(lookup_ip("example.com.", &config(addr))).unwrap();
+        assert_eq!(ips, vec!["192.0.2.1".parse::<IpAddr>().unwrap()]);
+        handle.join().unwrap();
+
+        // an ip needs no name server
+        let ips = block_on(lookup_ip("::1", &config(addr))).unwrap();
+        assert_eq!(ips, vec!["::1".parse::<IpAddr>().unwrap()]);
+    }
+
+    #[test]
+    fn test_query_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut length = [0u8; 2];
+            stream.read_exact(&mut length).unwrap();
+            let mut buf = vec![0u8; u16::from_be_bytes(length) as usize];
+            stream.read_exact(&mut buf).unwrap();
+            let reply = answer(&buf);
+            stream
+                .write_all(&(reply.len() as u16).to_be_bytes())
+                .unwrap();
+            stream.write_all(&reply).unwrap();
+        });
+
+        let mut config = config(addr);
+        config.tcp = true;
+        let response = block_on(query("example.com", Qtype::A, &config)).unwrap();
+        assert_eq!(response.ips().len(), 1);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_query_tcp_foreign_reply() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut length = [0u8; 2];
+            stream.read_exact(&mut length).unwrap();
+            let mut buf = vec![0u8; u16::from_be_bytes(length) as usize];
+            stream.read_exact(&mut buf).unwrap();
+            // the reply has another ID than the query
+            let mut reply = answer(&buf);
+            reply[0] ^= 0xff;
+            stream
+                .write_all(&(reply.len() as u16).to_be_bytes())
+                .unwrap();
+            stream.write_all(&reply).unwrap();
+        });
+
+        let mut config = config(addr);
+        config.tcp = true;
+        assert!(matches!(
+            block_on(query("example.com", Qtype::A, &config)),
+            Err(CustomError::ResponseError)
+        ));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_query_timeout() {
+        let server = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+
+        match block_on(query("example.com", Qtype::A, &config(addr))) {
+            Err(CustomError::Timeout(server)) => assert_eq!(server, addr),
+            _ => panic!("expected a timeout"),
+        }
+    }
+
+    #[test]
+    fn test_query_cancelled() {
+        let server = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+
+        let mut config = config(addr);
+        config.timeout = None;
+        // without a timeout of its own the query waits until it is dropped
+        let cancelled = block_on(async {
+            time::timeout(
+                Duration::from_millis(100),
+                query("example.com", Qtype::A, &config),
+            )
+            .await
+        });
+        assert!(cancelled.is_err());
+    }
+
+    #[test]
+    fn test_futures_are_send() {
+        fn assert_send<T: Send>(_: T) {}
+        let config = ResolverConfig::new("127.0.0.1".parse().unwrap());
+        assert_send(lookup("example.com", &config));
+        assert_send(lookup_ip("example.com", &config));
+        assert_send(query_all(&[], &config, Some(&mut CookieJar::new())));
+    }
+}
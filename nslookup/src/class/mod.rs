@@ -0,0 +1,109 @@
+use crate::customerror::CustomError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Variation of Class
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Class {
+    /// the Internet
+    IN,
+    /// Chaos, used by servers to tell their name and version
+    CH,
+    /// Hesiod
+    HS,
+    /// any class, only in questions
+    ANY,
+    /// any other class, kept by its number (RFC 3597)
+    Unknown(u16),
+}
+
+/// All Classes that can be asked for by their mnemonic
+const CLASSES: [Class; 4] = [Class::IN, Class::CH, Class::HS, Class::ANY];
+
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            Class::IN => write!(f, "IN"),
+            Class::CH => write!(f, "CH"),
+            Class::HS => write!(f, "HS"),
+            Class::ANY => write!(f, "ANY"),
+            Class::Unknown(x) => write!(f, "CLASS{}", x),
+        }
+    }
+}
+
+impl Class {
+    /// Returns the the Enum value
+    pub fn value(&self) -> u16 {
+        match *self {
+            Class::IN => 1,
+            Class::CH => 3,
+            Class::HS => 4,
+            Class::ANY => 255,
+            Class::Unknown(x) => x,
+        }
+    }
+
+    /// Returns Enum variant for given value.
+    /// Values without an own variant become Unknown.
+    ///
+    /// # Arguments
+    /// `value` - actual Class value
+    pub fn get_class(value: u16) -> Self {
+        match value {
+            1 => Class::IN,
+            3 => Class::CH,
+            4 => Class::HS,
+            255 => Class::ANY,
+            x => Class::Unknown(x),
+        }
+    }
+}
+
+impl FromStr for Class {
+    type Err = CustomError;
+
+    /// Returns Enum variant for the given mnemonic (e.g. "CH"), case insensitive.
+    /// Any class can be given as CLASS followed by its number (e.g. "CLASS254").
+    ///
+    /// # Arguments
+    /// `s` - the mnemonic
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(c) = CLASSES
+            .iter()
+            .find(|c| c.to_string().eq_ignore_ascii_case(s))
+        {
+            return Ok(*c);
+        }
+        match s.get(..5) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("CLASS") => s[5..]
+                .parse::<u16>()
+                .map(Class::get_class)
+                .map_err(|_| CustomError::UnknownClass(String::from(s))),
+            _ => Err(CustomError::UnknownClass(String::from(s))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("ch".parse::<Class>().unwrap(), Class::CH);
+        assert_eq!("IN".parse::<Class>().unwrap(), Class::IN);
+        assert_eq!("CLASS4".parse::<Class>().unwrap(), Class::HS);
+        assert_eq!("class254".parse::<Class>().unwrap(), Class::Unknown(254));
+        assert!("CLASS65536".parse::<Class>().is_err());
+        assert!("FOO".parse::<Class>().is_err());
+    }
+
+    #[test]
+    fn test_value() {
+        assert_eq!(Class::CH.value(), 3);
+        assert_eq!(Class::get_class(3), Class::CH);
+        assert_eq!(Class::Unknown(254).to_string(), "CLASS254");
+    }
+}
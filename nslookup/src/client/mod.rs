@@ -0,0 +1,912 @@
+use crate::config::ResolverConfig;
+use crate::cookie::CookieJar;
+use crate::customerror::CustomError;
+use crate::header::Header;
+use crate::qtype::Qtype;
+use crate::rcode::Rcode;
+use crate::response::{get_questions, Message};
+use std::collections::hash_map::RandomState;
+use std::convert::TryFrom;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream, UdpSocket};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use std::vec::Vec;
+
+/// The name server the next query starts with if the config has rotate set
+static NEXT_SERVER: AtomicUsize = AtomicUsize::new(0);
+
+/// Sends a DNS-Message to the name servers of the config and returns the first raw reply.
+/// The name servers are asked one after another, the whole list is tried `attempts` times.
+/// Every name server gets `timeout` to answer.
+/// If no name server answers the last error is returned, e.g. which server timed out.
+/// The query is sent over UDP unless the config asks for TCP,
+/// a truncated UDP reply is thrown away and the query is repeated over TCP.
+///
+/// # Arguments
+///
+/// * `message` - The DNS-Message containing Header and Question
+/// * `config` - The name servers to ask
+pub fn query(message: &[u8], config: &ResolverConfig) -> Result<Vec<u8>, CustomError> {
+    Ok(query_all(&[message.to_vec()], config, None)?.remove(0))
+}
+
+/// Sends a DNS-Message like query does, but with DNS Cookies (RFC 7873).
+/// Every server gets the COOKIE option with its cookies from the jar, EDNS is added if the message has none.
+/// The server cookie of the reply is stored in the jar, a reply with a foreign client cookie counts as no reply.
+/// If the server answers BADCOOKIE, the query is sent once more with the new server cookie.
+///
+/// # Arguments
+///
+/// * `message` - The DNS-Message containing Header and Question
+/// * `config` - The name servers to ask
+/// * `cookies` - The cookies of the name servers, updated with the replies
+pub fn query_with_cookies(
+    message: &[u8],
+    config: &ResolverConfig,
+    cookies: &mut CookieJar,
+) -> Result<Vec<u8>, CustomError> {
+    Ok(query_all(&[message.to_vec()], config, Some(cookies))?.remove(0))
+}
+
+/// Sends several DNS-Messages at once and returns the replies in the order of the messages.
+/// Over UDP all messages are sent from one socket before the first reply is awaited,
+/// the replies are matched to the messages by ID and question.
+/// Messages that a name server doesn't answer in time are sent to the next one like query does,
+/// the last error is returned if a message isn't answered at all.
+///
+/// # Arguments
+///
+/// * `messages` - The DNS-Messages, e.g. the A and AAAA question of a name
+/// * `config` - The name servers to ask
+/// * `cookies` - The cookies of the name servers, None to send no cookies
+pub fn query_all(
+    messages: &[Vec<u8>],
+    config: &ResolverConfig,
+    mut cookies: Option<&mut CookieJar>,
+) -> Result<Vec<Vec<u8>>, CustomError> {
+    let mut replies = vec![None; messages.len()];
+    exchange(config, |server| {
+        send_all(
+            messages,
+            &mut replies,
+            server,
+            config,
+            cookies.as_deref_mut(),
+        )
+    })?;
+    Ok(replies.into_iter().flatten().collect())
+}
+
+/// Asks the name servers of the config one after another until one of them succeeds.
+///
+/// # Arguments
+///
+/// * `config` - The name servers to ask
+/// * `send` - Sends the query to a single name server
+fn exchange<T, F>(config: &ResolverConfig, mut send: F) -> Result<T, CustomError>
+where
+    F: FnMut(&SocketAddr) -> Result<T, CustomError>,
+{
+    let mut last_error = CustomError::NoNameserver;
+    for server in server_order(config)? {
+        match send(&server) {
+            Ok(result) => return Ok(result),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+/// Returns the name servers in the order they are asked.
+/// The whole list is repeated `attempts` times, starting with the next server if the config has rotate set.
+///
+/// # Arguments
+///
+/// * `config` - The name servers to ask
+pub(crate) fn server_order(config: &ResolverConfig) -> Result<Vec<SocketAddr>, CustomError> {
+    let servers = config.server_addrs();
+    if servers.is_empty() {
+        return Err(CustomError::NoNameserver);
+    }
+
+    let start = if config.rotate {
+        NEXT_SERVER.fetch_add(1, Ordering::Relaxed)
+    } else {
+        0
+    };
+    Ok((0..config.attempts.max(1) * servers.len())
+        .map(|i| servers[(start + i) % servers.len()])
+        .collect())
+}
+
+/// Returns a random number.
+/// Every RandomState is seeded with new random keys, hashing nothing with it gives a random number.
+pub fn random_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+/// Returns a random ID for a DNS-Message.
+/// A random ID makes it harder to spoof a reply.
+pub fn random_id() -> u16 {
+    random_u64() as u16
+}
+
+/// A message that was sent to a name server and waits for its reply
+struct Query {
+    /// The position of the message
+    index: usize,
+    /// The message as it was sent, with the cookies of the server
+    query: Vec<u8>,
+    /// True if the message was already sent again after BADCOOKIE
+    resent: bool,
+    /// True if the message was sent over TCP
+    tcp: bool,
+}
+
+/// What has to be done after a reply arrived
+#[derive(Debug, PartialEq)]
+pub(crate) enum Action {
+    /// The reply doesn't belong to a message or has a foreign client cookie, it is dropped
+    Ignore,
+    /// The reply is truncated, the query has to be sent over TCP
+    Tcp(Vec<u8>),
+    /// The server answered BADCOOKIE, the query has to be sent again with the new server cookie
+    Resend(Vec<u8>),
+    /// The reply is stored
+    Done,
+}
+
+/// The messages that were sent to a single name server and wait for their replies.
+/// It decides what happens with a reply, the clients only send and receive.
+pub(crate) struct Pending<'a> {
+    messages: &'a [Vec<u8>],
+    replies: &'a mut [Option<Vec<u8>>],
+    server: SocketAddr,
+    cookies: Option<&'a mut CookieJar>,
+    tcp: bool,
+    queries: Vec<Query>,
+}
+
+impl<'a> Pending<'a> {
+    /// Returns the Pending messages of a name server, nothing is sent yet
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The DNS-Messages
+    /// * `replies` - The replies of the messages, None if there is none yet
+    /// * `server` - The socket adress of the name server
+    /// * `cookies` - The cookies of the name servers, None to send no cookies
+    /// * `tcp` - True if the messages are sent over TCP
+    pub(crate) fn new(
+        messages: &'a [Vec<u8>],
+        replies: &'a mut [Option<Vec<u8>>],
+        server: SocketAddr,
+        cookies: Option<&'a mut CookieJar>,
+        tcp: bool,
+    ) -> Self {
+        Pending {
+            messages,
+            replies,
+            server,
+            cookies,
+            tcp,
+            queries: vec![],
+        }
+    }
+
+    /// Returns the positions of the messages without a reply
+    pub(crate) fn unanswered(&self) -> Vec<usize> {
+        (0..self.messages.len())
+            .filter(|&i| self.replies[i].is_none())
+            .collect()
+    }
+
+    /// Returns the query of a message as it is sent to the server and waits for its reply
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The position of the message
+    pub(crate) fn send(&mut self, index: usize) -> Result<Vec<u8>, CustomError> {
+        let query = prepare(
+            &self.messages[index],
+            &self.server,
+            self.cookies.as_deref_mut(),
+        )?;
+        self.queries.push(Query {
+            index,
+            query: query.clone(),
+            resent: false,
+            tcp: self.tcp,
+        });
+        Ok(query)
+    }
+
+    /// Returns true if every message has its reply
+    pub(crate) fn is_done(&self) -> bool {
+        self.queries.is_empty()
+    }
+
+    /// Handles a reply and returns what has to be done next.
+    /// A reply only belongs to a message if it comes from the server and has the ID and question of the query.
+    ///
+    /// # Arguments
+    ///
+    /// * `reply` - The raw reply
+    /// * `src` - The socket adress the reply came from
+    pub(crate) fn on_reply(
+        &mut self,
+        reply: Vec<u8>,
+        src: SocketAddr,
+    ) -> Result<Action, CustomError> {
+        let position = match self
+            .queries
+            .iter()
+            .position(|q| src == self.server && is_reply_to(&q.query, &reply))
+        {
+            Some(position) => position,
+            None => return Ok(Action::Ignore),
+        };
+
+        let query = &mut self.queries[position];
+        // a reply that is too short for a header is left to the parser to report
+        if !query.tcp && Header::parse(&reply).map(|h| h.tc).unwrap_or(false) {
+            query.tcp = true;
+            return Ok(Action::Tcp(query.query.clone()));
+        }
+        match accept(&reply, &self.server, self.cookies.as_deref_mut()) {
+            // a foreign client cookie, the reply may be spoofed
+            Err(_) => Ok(Action::Ignore),
+            Ok(true) if !query.resent => {
+                query.query = prepare(
+                    &self.messages[query.index],
+                    &self.server,
+                    self.cookies.as_deref_mut(),
+                )?;
+                query.resent = true;
+                query.tcp = self.tcp;
+                Ok(Action::Resend(query.query.clone()))
+            }
+            Ok(_) => {
+                let answered = self.queries.remove(position);
+                self.replies[answered.index] = Some(reply);
+                Ok(Action::Done)
+            }
+        }
+    }
+}
+
+/// Sends all messages without a reply to a single name server and stores the replies.
+/// Uses TCP if the config says so or a UDP reply has the truncated flag set.
+/// Returns an error if a message isn't answered, the replies received so far are kept.
+///
+/// # Arguments
+///
+/// * `messages` - The DNS-Messages
+/// * `replies` - The replies of the messages, None if there is none yet
+/// * `server` - The socket adress of the name server
+/// * `config` - The config containing the transport and the timeout
+/// * `cookies` - The cookies of the name servers, None to send no cookies
+fn send_all(
+    messages: &[Vec<u8>],
+    replies: &mut [Option<Vec<u8>>],
+    server: &SocketAddr,
+    config: &ResolverConfig,
+    cookies: Option<&mut CookieJar>,
+) -> Result<(), CustomError> {
+    let mut pending = Pending::new(messages, replies, *server, cookies, config.tcp);
+    if config.tcp {
+        for index in pending.unanswered() {
+            let query = pending.send(index)?;
+            let reply = send_tcp(&query, server, config)?;
+            on_tcp_reply(&mut pending, reply, server, config)?;
+        }
+        return Ok(());
+    }
+
+    let bind = if server.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let sock = UdpSocket::bind(bind)?;
+    for index in pending.unanswered() {
+        sock.send_to(&pending.send(index)?, server)?;
+    }
+
+    // the reply may be as large as the payload size advertised with EDNS
+    let mut buf = vec![0u8; usize::from(u16::MAX)];
+    // Replies that don't belong to a query are dropped, the timeout applies to the whole wait
+    let deadline = config.timeout.map(|timeout| Instant::now() + timeout);
+    while !pending.is_done() {
+        if let Some(deadline) = deadline {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(CustomError::Timeout(*server));
+            }
+            sock.set_read_timeout(Some(deadline - now))?;
+        }
+        let (amt, src) = sock
+            .recv_from(&mut buf)
+            .map_err(|e| timeout_error(e, server))?;
+        match pending.on_reply(buf[..amt].to_vec(), src)? {
+            Action::Tcp(query) => {
+                let reply = send_tcp(&query, server, config)?;
+                if let Some(query) = on_tcp_reply(&mut pending, reply, server, config)? {
+                    sock.send_to(&query, server)?;
+                }
+            }
+            Action::Resend(query) => {
+                sock.send_to(&query, server)?;
+            }
+            Action::Ignore | Action::Done => {}
+        }
+    }
+    Ok(())
+}
+
+/// Handles a reply that came over TCP.
+/// A reply that doesn't belong to the query is an error, a query that has to be sent again
+/// after BADCOOKIE is sent over TCP if the config says so and returned for UDP otherwise.
+///
+/// # Arguments
+///
+/// * `pending` - The messages that wait for their replies
+/// * `reply` - The raw reply
+/// * `server` - The socket adress of the name server
+/// * `config` - The config containing the transport and the timeout
+fn on_tcp_reply(
+    pending: &mut Pending,
+    mut reply: Vec<u8>,
+    server: &SocketAddr,
+    config: &ResolverConfig,
+) -> Result<Option<Vec<u8>>, CustomError> {
+    loop {
+        match pending.on_reply(reply, *server)? {
+            Action::Done => return Ok(None),
+            Action::Ignore => return Err(CustomError::ResponseError),
+            Action::Resend(query) if !config.tcp => return Ok(Some(query)),
+            Action::Resend(query) | Action::Tcp(query) => {
+                reply = send_tcp(&query, server, config)?;
+            }
+        }
+    }
+}
+
+/// Returns the message as it is sent to a server, with the cookies of the server if cookies are used
+///
+/// # Arguments
+///
+/// * `message` - The DNS-Message containing Header and Question
+/// * `server` - The socket adress of the name server
+/// * `cookies` - The cookies of the name servers, None to send no cookies
+fn prepare(
+    message: &[u8],
+    server: &SocketAddr,
+    cookies: Option<&mut CookieJar>,
+) -> Result<Vec<u8>, CustomError> {
+    match cookies {
+        Some(cookies) => cookies.add_cookie(message, server),
+        None => Ok(message.to_vec()),
+    }
+}
+
+/// Stores the server cookie of a reply if cookies are used.
+/// Returns true if the server answered BADCOOKIE, then the query has to be sent again with the new cookie.
+/// Returns an error if the reply has a foreign client cookie.
+///
+/// # Arguments
+///
+/// * `reply` - The raw reply
+/// * `server` - The socket adress of the name server
+/// * `cookies` - The cookies of the name servers, None to send no cookies
+fn accept(
+    reply: &[u8],
+    server: &SocketAddr,
+    cookies: Option<&mut CookieJar>,
+) -> Result<bool, CustomError> {
+    let cookies = match cookies {
+        Some(cookies) => cookies,
+        None => return Ok(false),
+    };
+    // a reply that can't be parsed is left to the parser to report
+    match Message::parse(reply) {
+        Ok(message) => {
+            if let Some(edns) = &message.edns {
+                cookies.update(server, edns)?;
+            }
+            Ok(message.rcode() == Rcode::BadCookie)
+        }
+        Err(_) => Ok(false),
+    }
+}
+
+/// Returns true if a reply belongs to a query.
+/// The ID and the questions have to be the same, the case of the names may differ.
+///
+/// # Arguments
+///
+/// * `query` - The DNS-Message that was sent
+/// * `reply` - The DNS-Message that was received
+pub fn is_reply_to(query: &[u8], reply: &[u8]) -> bool {
+    let parse = |message: &[u8]| -> Result<_, CustomError> {
+        let header = Header::parse(message)?;
+        let (questions, _) = get_questions(message, 12, header.qdcount)?;
+        Ok((header.id, questions))
+    };
+    match (parse(query), parse(reply)) {
+        (Ok((id, asked)), Ok((reply_id, answered))) => {
+            id == reply_id
+                && asked.len() == answered.len()
+                && asked.iter().zip(&answered).all(|(a, b)| {
+                    a.name.eq_ignore_ascii_case(&b.name) && a.qtype == b.qtype && a.class == b.class
+                })
+        }
+        _ => false,
+    }
+}
+
+/// Turns the error of a socket that waited too long into a Timeout naming the server
+///
+/// # Arguments
+///
+/// * `error` - The error of the socket
+/// * `server` - The socket adress of the name server
+fn timeout_error(error: io::Error, server: &SocketAddr) -> CustomError {
+    match error.kind() {
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => CustomError::Timeout(*server),
+        _ => CustomError::IoError(error),
+    }
+}
+
+/// Sends a DNS-Message over TCP to a single name server and returns the raw reply.
+/// Both messages are prefixed with their length as two bytes (RFC 1035 4.2.2).
+///
+/// # Arguments
+///
+/// * `message` - The DNS-Message containing Header and Question
+/// * `server` - The socket adress of the name server
+/// * `config` - The config containing the timeout
+fn send_tcp(
+    message: &[u8],
+    server: &SocketAddr,
+    config: &ResolverConfig,
+) -> Result<Vec<u8>, CustomError> {
+    let mut stream = connect_tcp(message, server, config)?;
+    read_tcp(&mut stream, server)
+}
+
+/// Returns a DNS-Message prefixed with its length as two bytes, the way it is sent over TCP (RFC 1035 4.2.2)
+///
+/// # Arguments
+///
+/// * `message` - The DNS-Message
+pub(crate) fn frame(message: &[u8]) -> Result<Vec<u8>, CustomError> {
+    let length = u16::try_from(message.len()).map_err(|_| CustomError::Overflow)?;
+    let mut framed = Vec::with_capacity(message.len() + 2);
+    framed.extend_from_slice(&length.to_be_bytes());
+    framed.extend_from_slice(message);
+    Ok(framed)
+}
+
+/// Asks the name servers of the config for a zone transfer (AXFR) and returns the raw replies.
+/// A zone is sent over TCP in one or more messages, it starts and ends with the SOA record of the zone.
+/// If the server refuses the transfer, the reply with the Rcode is the only one returned.
+///
+/// # Arguments
+///
+/// * `message` - The DNS-Message containing Header and the AXFR Question
+/// * `config` - The name servers to ask
+pub fn transfer(message: &[u8], config: &ResolverConfig) -> Result<Vec<Vec<u8>>, CustomError> {
+    let id = Header::parse(message)?.id;
+    exchange(config, |server| {
+        let mut stream = connect_tcp(message, server, config)?;
+        let mut replies = vec![];
+        let mut soa_records = 0;
+        while soa_records < 2 {
+            let reply = read_tcp(&mut stream, server)?;
+            let parsed = Message::parse(&reply)?;
+            if parsed.header.id != id || (replies.is_empty() && !is_reply_to(message, &reply)) {
+                return Err(CustomError::ResponseError);
+            }
+            if parsed.rcode() != Rcode::NoError {
+                return Ok(vec![reply]);
+            }
+            // every message has to carry records, else the transfer would never end
+            if parsed.answers.is_empty() {
+                return Err(CustomError::ResponseError);
+            }
+            soa_records += parsed
+                .answers
+                .iter()
+                .filter(|r| r.rtype == Qtype::SOA)
+                .count();
+            replies.push(reply);
+        }
+        Ok(replies)
+    })
+}
+
+/// Connects to a name server over TCP and sends a DNS-Message prefixed with its length
+///
+/// # Arguments
+///
+/// * `message` - The DNS-Message containing Header and Question
+/// * `server` - The socket adress of the name server
+/// * `config` - The config containing the timeout
+fn connect_tcp(
+    message: &[u8],
+    server: &SocketAddr,
+    config: &ResolverConfig,
+) -> Result<TcpStream, CustomError> {
+    let framed = frame(message)?;
+    let mut stream = match config.timeout {
+        Some(timeout) => TcpStream::connect_timeout(server, timeout),
+        None => TcpStream::connect(server),
+    }
+    .map_err(|e| timeout_error(e, server))?;
+    stream.set_read_timeout(config.timeout)?;
+    stream.set_write_timeout(config.timeout)?;
+    stream
+        .write_all(&framed)
+        .map_err(|e| timeout_error(e, server))?;
+    Ok(stream)
+}
+
+/// Reads a single DNS-Message prefixed with its length from a TCP stream
+///
+/// # Arguments
+///
+/// * `stream` - The connection to the name server
+/// * `server` - The socket adress of the name server
+fn read_tcp(stream: &mut TcpStream, server: &SocketAddr) -> Result<Vec<u8>, CustomError> {
+    let mut length = [0u8; 2];
+    stream
+        .read_exact(&mut length)
+        .map_err(|e| timeout_error(e, server))?;
+    let mut buf = vec![0u8; u16::from_be_bytes(length) as usize];
+    stream
+        .read_exact(&mut buf)
+        .map_err(|e| timeout_error(e, server))?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cookie::OPTION_COOKIE;
+    use crate::edns::Edns;
+    use crate::question::Question;
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Duration;
+
+    /// Returns a query for the A records of a name
+    fn message(id: u16, name: &str) -> Vec<u8> {
+        let question = Question::new(name, Qtype::A).unwrap().get_question();
+        [Header::new(id, false, 0).get_header(), question].concat()
+    }
+
+    /// Answers a single UDP query with the query itself
+    fn echo_udp(server: UdpSocket) {
+        let mut buf = [0u8; 512];
+        let (amt, src) = server.recv_from(&mut buf).unwrap();
+        server.send_to(&buf[..amt], src).unwrap();
+    }
+
+    /// Answers a single TCP query with the query itself, the truncated flag is cleared
+    fn serve_tcp(listener: TcpListener) {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut length = [0u8; 2];
+        stream.read_exact(&mut length).unwrap();
+        let mut buf = vec![0u8; u16::from_be_bytes(length) as usize];
+        stream.read_exact(&mut buf).unwrap();
+        buf[2] &= !0x02;
+        stream.write_all(&length).unwrap();
+        stream.write_all(&buf).unwrap();
+    }
+
+    #[test]
+    fn test_query_local_server() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = thread::spawn(move || echo_udp(server));
+
+        let mut config = ResolverConfig::new(addr.ip());
+        config.port = addr.port();
+        let message = message(random_id(), "example.com");
+        assert_eq!(query(&message, &config).unwrap(), message);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_query_next_server() {
+        // The test needs two adresses with the same port, Linux routes all of 127.0.0.0/8 to the loopback interface
+        let server = UdpSocket::bind("127.0.0.2:0")
+            .expect("127.0.0.2 isn't routed to the loopback interface");
+        let port = server.local_addr().unwrap().port();
+        let handle = thread::spawn(move || echo_udp(server));
+
+        let mut config = ResolverConfig::new("127.0.0.1".parse().unwrap());
+        config.nameservers.push("127.0.0.2".parse().unwrap());
+        config.port = port;
+        config.timeout = Some(Duration::from_millis(200));
+        let message = message(4, "example.com");
+        assert_eq!(query(&message, &config).unwrap(), message);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_query_all() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            // both queries arrive before the first reply is sent, the replies come in reverse order
+            let mut queries = vec![];
+            for _ in 0..2 {
+                let mut buf = [0u8; 512];
+                let (amt, src) = server.recv_from(&mut buf).unwrap();
+                queries.push((buf[..amt].to_vec(), src));
+            }
+            for (query, src) in queries.iter().rev() {
+                server.send_to(query, src).unwrap();
+            }
+        });
+
+        let mut config = ResolverConfig::new(addr.ip());
+        config.port = addr.port();
+        let messages = vec![message(10, "example.com"), message(11, "example.org")];
+        assert_eq!(query_all(&messages, &config, None).unwrap(), messages);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_query_drops_foreign_replies() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            let mut buf = [0u8; 512];
+            let (amt, src) = server.recv_from(&mut buf).unwrap();
+            // wrong source, wrong ID and wrong question before the real reply
+            let spoofer = UdpSocket::bind("127.0.0.1:0").unwrap();
+            spoofer.send_to(&buf[..amt], src).unwrap();
+            server.send_to(&message(6, "example.com"), src).unwrap();
+            server.send_to(&message(5, "example.org"), src).unwrap();
+            server.send_to(&buf[..amt], src).unwrap();
+        });
+
+        let mut config = ResolverConfig::new(addr.ip());
+        config.port = addr.port();
+        let message = message(5, "example.com");
+        assert_eq!(query(&message, &config).unwrap(), message);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_query_timeout() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+
+        let mut config = ResolverConfig::new(addr.ip());
+        config.port = addr.port();
+        config.timeout = Some(Duration::from_millis(100));
+        config.attempts = 1;
+        match query(&message(7, "example.com"), &config) {
+            Err(CustomError::Timeout(server)) => assert_eq!(server, addr),
+            _ => panic!("expected a timeout"),
+        }
+    }
+
+    #[test]
+    fn test_query_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = thread::spawn(move || serve_tcp(listener));
+
+        let mut config = ResolverConfig::new(addr.ip());
+        config.port = addr.port();
+        config.tcp = true;
+        let message = message(3, "example.com");
+        assert_eq!(query(&message, &config).unwrap(), message);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_query_tcp_foreign_reply() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut length = [0u8; 2];
+            stream.read_exact(&mut length).unwrap();
+            let mut buf = vec![0u8; u16::from_be_bytes(length) as usize];
+            stream.read_exact(&mut buf).unwrap();
+            // the reply has another ID than the query
+            let reply = message(13, "example.com");
+            stream
+                .write_all(&(reply.len() as u16).to_be_bytes())
+                .unwrap();
+            stream.write_all(&reply).unwrap();
+        });
+
+        let mut config = ResolverConfig::new(addr.ip());
+        config.port = addr.port();
+        config.tcp = true;
+        config.attempts = 1;
+        assert!(matches!(
+            query(&message(12, "example.com"), &config),
+            Err(CustomError::ResponseError)
+        ));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_query_truncated() {
+        // The port may already be used for UDP by another program, then another port is tried
+        let (listener, server) = (0..10)
+            .find_map(|_| {
+                let listener = TcpListener::bind("127.0.0.1:0").ok()?;
+                let server = UdpSocket::bind(listener.local_addr().ok()?).ok()?;
+                Some((listener, server))
+            })
+            .expect("no port is free for both TCP and UDP");
+        let addr = listener.local_addr().unwrap();
+        let udp = thread::spawn(move || {
+            let mut buf = [0u8; 512];
+            let (amt, src) = server.recv_from(&mut buf).unwrap();
+            buf[2] |= 0x02;
+            server.send_to(&buf[..amt], src).unwrap();
+        });
+        let tcp = thread::spawn(move || serve_tcp(listener));
+
+        let mut config = ResolverConfig::new(addr.ip());
+        config.port = addr.port();
+        let message = message(2, "example.com");
+        assert_eq!(query(&message, &config).unwrap(), message);
+        udp.join().unwrap();
+        tcp.join().unwrap();
+    }
+
+    #[test]
+    fn test_query_with_cookies() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            let server_cookie = vec![5u8; 8];
+            // the first query only has the client cookie and gets BADCOOKIE with a server cookie
+            for rcode in &[Rcode::BadCookie, Rcode::NoError] {
+                let mut buf = [0u8; 512];
+                let (amt, src) = server.recv_from(&mut buf).unwrap();
+                let query = Message::parse(&buf[..amt]).unwrap();
+                let cookie = query
+                    .edns
+                    .unwrap()
+                    .option(OPTION_COOKIE)
+                    .unwrap()
+                    .data
+                    .clone();
+                let expected = if *rcode == Rcode::BadCookie { 8 } else { 16 };
+                assert_eq!(cookie.len(), expected);
+
+                let mut edns = Edns::new(1232)
+                    .with_option(OPTION_COOKIE, [&cookie[..8], &server_cookie].concat());
+                edns.extended_rcode = (rcode.value() >> 4) as u8;
+                let header = Header::new(query.header.id, true, 0)
+                    .with_rcode(*rcode)
+                    .with_counts(1, 0, 0, 1);
+                let reply = [&header.get_header()[..], &buf[12..29], &edns.to_wire()].concat();
+                server.send_to(&reply, src).unwrap();
+            }
+        });
+
+        let mut config = ResolverConfig::new(addr.ip());
+        config.port = addr.port();
+        let mut cookies = CookieJar::new();
+        let reply = query_with_cookies(&message(9, "example.com"), &config, &mut cookies).unwrap();
+        handle.join().unwrap();
+        assert_eq!(Message::parse(&reply).unwrap().rcode(), Rcode::NoError);
+        assert_eq!(cookies.server_cookie(&addr), Some(&[5u8; 8][..]));
+    }
+
+    #[test]
+    fn test_pending_on_reply() {
+        let server: SocketAddr = "127.0.0.1:53".parse().unwrap();
+        let messages = vec![message(14, "example.com"), message(15, "example.org")];
+        let mut replies = vec![None, None];
+        let mut pending = Pending::new(&messages, &mut replies, server, None, false);
+        assert_eq!(pending.unanswered(), vec![0, 1]);
+        assert_eq!(pending.send(0).unwrap(), messages[0]);
+        assert_eq!(pending.send(1).unwrap(), messages[1]);
+
+        // another source and another ID
+        let spoofer = "127.0.0.2:53".parse().unwrap();
+        let action = pending.on_reply(messages[0].clone(), spoofer).unwrap();
+        assert_eq!(action, Action::Ignore);
+        let action = pending
+            .on_reply(message(16, "example.com"), server)
+            .unwrap();
+        assert_eq!(action, Action::Ignore);
+        // a truncated reply is asked again over TCP, the TCP reply is kept even if it is truncated too
+        let mut truncated = messages[1].clone();
+        truncated[2] |= 0x02;
+        let action = pending.on_reply(truncated.clone(), server).unwrap();
+        assert_eq!(action, Action::Tcp(messages[1].clone()));
+        let action = pending.on_reply(truncated.clone(), server).unwrap();
+        assert_eq!(action, Action::Done);
+        let action = pending.on_reply(messages[0].clone(), server).unwrap();
+        assert_eq!(action, Action::Done);
+        assert!(pending.is_done());
+        assert_eq!(replies, vec![Some(messages[0].clone()), Some(truncated)]);
+    }
+
+    #[test]
+    fn test_frame() {
+        assert_eq!(frame(&[1, 2, 3]).unwrap(), vec![0, 3, 1, 2, 3]);
+        assert!(matches!(frame(&vec![0; 65536]), Err(CustomError::Overflow)));
+    }
+
+    #[test]
+    fn test_is_reply_to() {
+        let query = message(8, "example.com");
+        assert!(is_reply_to(&query, &message(8, "ExAmple.COM")));
+        assert!(!is_reply_to(&query, &message(9, "example.com")));
+        assert!(!is_reply_to(&query, &message(8, "example.org")));
+        assert!(!is_reply_to(&query, &query[..12]));
+    }
+
+    #[test]
+    fn test_transfer() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut length = [0u8; 2];
+            stream.read_exact(&mut length).unwrap();
+            let mut query = vec![0u8; u16::from_be_bytes(length) as usize];
+            stream.read_exact(&mut query).unwrap();
+
+            // records of the root name, the SOA has an empty mname and rname
+            let soa = [&[0, 0, 6, 0, 1, 0, 0, 0, 60, 0, 22, 0, 0][..], &[0; 20]].concat();
+            let a = vec![0, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 192, 0, 2, 1];
+            let mut first = query.clone();
+            first[2] |= 0x80;
+            first[7] = 2;
+            first.extend_from_slice(&soa);
+            first.extend_from_slice(&a);
+            // later messages may leave out the question
+            let mut last = first[..12].to_vec();
+            last[5] = 0;
+            last[7] = 1;
+            last.extend_from_slice(&soa);
+            for reply in [first, last].iter() {
+                stream
+                    .write_all(&(reply.len() as u16).to_be_bytes())
+                    .unwrap();
+                stream.write_all(reply).unwrap();
+            }
+        });
+
+        let mut config = ResolverConfig::new(addr.ip());
+        config.port = addr.port();
+        config.attempts = 1;
+        let question = Question::new(".", Qtype::AXFR).unwrap().get_question();
+        let query = [Header::new(8, false, 0).get_header(), question].concat();
+        let replies = transfer(&query, &config).unwrap();
+        assert_eq!(replies.len(), 2);
+        let records = replies
+            .iter()
+            .flat_map(|reply| Message::parse(reply).unwrap().answers)
+            .map(|record| record.rtype)
+            .collect::<Vec<_>>();
+        assert!(records == vec![Qtype::SOA, Qtype::A, Qtype::SOA]);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_query_no_nameserver() {
+        let mut config = ResolverConfig::new("127.0.0.1".parse().unwrap());
+        config.nameservers.clear();
+        assert!(query(&[1, 2, 3], &config).is_err());
+    }
+}
@@ -0,0 +1,342 @@
+use crate::customerror::CustomError;
+use crate::rcode::Rcode;
+use std::fs;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::time::Duration;
+
+/// The name server that is used if no other server is given
+pub const DEFAULT_SERVER: [u8; 4] = [8, 8, 8, 8];
+
+/// The port name servers listen on
+pub const DEFAULT_PORT: u16 = 53;
+
+/// The file the system resolver is configured with
+pub const RESOLV_CONF: &str = "/etc/resolv.conf";
+
+/// The system resolver uses at most this many name servers
+const MAXNS: usize = 3;
+
+/// Configuration of the name servers that are queried
+#[derive(Debug, PartialEq, Clone)]
+pub struct ResolverConfig {
+    pub nameservers: Vec<IpAddr>,
+    pub port: u16,
+    pub timeout: Option<Duration>,
+    /// Domains that are appended to names with less than `ndots` dots
+    pub search: Vec<String>,
+    /// Names with at least this many dots are tried as absolute name first
+    pub ndots: usize,
+    /// How often every name server is asked before giving up
+    pub attempts: usize,
+    /// Spread the queries over all name servers instead of always starting with the first
+    pub rotate: bool,
+    /// Send every query over TCP instead of UDP first
+    pub tcp: bool,
+}
+
+impl ResolverConfig {
+    /// Returns a new ResolverConfig which queries a single name server on port 53
+    ///
+    /// # Arguments
+    ///
+    /// * `server` - IPv4 or IPv6 adress of the name server
+    pub fn new(server: IpAddr) -> Self {
+        ResolverConfig {
+            nameservers: vec![server],
+            port: DEFAULT_PORT,
+            timeout: Some(Duration::from_secs(5)),
+            search: vec![],
+            ndots: 1,
+            attempts: 2,
+            rotate: false,
+            tcp: false,
+        }
+    }
+
+    /// Reads a ResolverConfig from a file in resolv.conf format.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file, usually /etc/resolv.conf
+    pub fn from_resolv_conf<P: AsRef<Path>>(path: P) -> Result<Self, CustomError> {
+        Ok(ResolverConfig::parse_resolv_conf(&fs::read_to_string(
+            path,
+        )?))
+    }
+
+    /// Parses the content of a resolv.conf file.
+    /// Supported are the keywords nameserver, search, domain and options
+    /// with ndots, timeout, attempts, rotate and use-vc. Everything else is ignored like the system resolver does.
+    /// If no name server is given the local name server is used.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The content of the file
+    pub fn parse_resolv_conf(content: &str) -> Self {
+        let mut config = ResolverConfig::new(IpAddr::from([127, 0, 0, 1]));
+        config.nameservers.clear();
+
+        for line in content.lines() {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("nameserver") => {
+                    if let Some(Ok(ip)) = words.next().map(str::parse::<IpAddr>) {
+                        if config.nameservers.len() < MAXNS {
+                            config.nameservers.push(ip);
+                        }
+                    }
+                }
+                Some("domain") => {
+                    config.search = words.next().map(String::from).into_iter().collect();
+                }
+                Some("search") => {
+                    config.search = words.map(String::from).collect();
+                }
+                Some("options") => words.for_each(|option| config.set_option(option)),
+                _ => {}
+            }
+        }
+
+        if config.nameservers.is_empty() {
+            config.nameservers.push(IpAddr::from([127, 0, 0, 1]));
+        }
+        config
+    }
+
+    /// Applies a single resolv.conf option like "ndots:2".
+    /// Values are capped like the system resolver does, invalid options are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `option` - The option
+    fn set_option(&mut self, option: &str) {
+        let mut parts = option.splitn(2, ':');
+        let name = parts.next().unwrap_or("");
+        let value = parts.next().and_then(|v| v.parse::<usize>().ok());
+        match (name, value) {
+            ("ndots", Some(n)) => self.ndots = n.min(15),
+            ("timeout", Some(n)) => self.timeout = Some(Duration::from_secs(n.min(30) as u64)),
+            ("attempts", Some(n)) => self.attempts = n.min(5),
+            ("rotate", None) => self.rotate = true,
+            ("use-vc", None) => self.tcp = true,
+            _ => {}
+        }
+    }
+
+    /// Returns the names that are tried for a host name in the order they are tried.
+    /// A name with a trailing dot is absolute and never combined with the search list.
+    /// A name with at least `ndots` dots is tried as is before the search list is applied,
+    /// all others are tried as is after the search list.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The host name
+    pub fn candidate_names(&self, name: &str) -> Vec<String> {
+        if name.ends_with('.') {
+            return vec![String::from(name.trim_end_matches('.'))];
+        }
+
+        let searched = self
+            .search
+            .iter()
+            .map(|domain| format!("{}.{}", name, domain.trim_end_matches('.')));
+        if name.matches('.').count() >= self.ndots {
+            std::iter::once(String::from(name))
+                .chain(searched)
+                .collect()
+        } else {
+            searched
+                .chain(std::iter::once(String::from(name)))
+                .collect()
+        }
+    }
+
+    /// Returns the SearchList that tries the candidate names of a host name
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The host name
+    /// * `is_nodata` - Returns true if a result says the name exists but has no records of the type
+    pub fn search_list<T>(&self, name: &str, is_nodata: fn(&T) -> bool) -> SearchList<T> {
+        SearchList::new(self.candidate_names(name), is_nodata)
+    }
+
+    /// Returns the socket adresses of all name servers
+    pub fn server_addrs(&self) -> Vec<SocketAddr> {
+        self.nameservers
+            .iter()
+            .map(|&ip| SocketAddr::new(ip, self.port))
+            .collect()
+    }
+}
+
+/// Tries the candidate names of a host one after another until one of them has records of the type.
+/// The names are asked by the caller, so the blocking and the async client share the rules:
+/// a name that doesn't exist (NXDOMAIN), has an empty or faulty reply or has no records of the type (NODATA)
+/// leads to the next name, any other error ends the search.
+/// A NODATA result is only returned if no other name has records.
+/// The caller asks every name of next_name, hands the result to record until that returns the result
+/// of the search and calls finish if the names run out.
+pub struct SearchList<T> {
+    names: std::vec::IntoIter<String>,
+    is_nodata: fn(&T) -> bool,
+    nodata: Option<T>,
+    last_error: CustomError,
+}
+
+impl<T> SearchList<T> {
+    /// Returns a SearchList of the names
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - The names in the order they are tried
+    /// * `is_nodata` - Returns true if a result says the name exists but has no records of the type
+    pub fn new(names: Vec<String>, is_nodata: fn(&T) -> bool) -> Self {
+        SearchList {
+            names: names.into_iter(),
+            is_nodata,
+            nodata: None,
+            last_error: CustomError::EmptyResponse,
+        }
+    }
+
+    /// Returns the next name to ask, None if all names were tried
+    pub fn next_name(&mut self) -> Option<String> {
+        self.names.next()
+    }
+
+    /// Takes the result of the last name.
+    /// Returns the result of the search if it is over, None if the next name has to be asked.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - The result of the last name
+    pub fn record(&mut self, result: Result<T, CustomError>) -> Option<Result<T, CustomError>> {
+        match result {
+            Ok(found) if (self.is_nodata)(&found) => {
+                self.nodata.get_or_insert(found);
+                None
+            }
+            Ok(found) => Some(Ok(found)),
+            Err(e @ CustomError::ResponseError)
+            | Err(e @ CustomError::EmptyResponse)
+            | Err(e @ CustomError::Rcode(Rcode::NXDomain)) => {
+                self.last_error = e;
+                None
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    /// Returns the result after all names were tried without success:
+    /// the first NODATA result or the error of the last name
+    pub fn finish(self) -> Result<T, CustomError> {
+        match self.nodata {
+            Some(nodata) => Ok(nodata),
+            None => Err(self.last_error),
+        }
+    }
+}
+
+impl Default for ResolverConfig {
+    /// Returns the config of the system resolver or 8.8.8.8 if it can't be read
+    fn default() -> Self {
+        ResolverConfig::from_resolv_conf(RESOLV_CONF)
+            .unwrap_or_else(|_| ResolverConfig::new(IpAddr::from(DEFAULT_SERVER)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_addrs() {
+        let mut config = ResolverConfig::new("2001:4860:4860::8888".parse().unwrap());
+        config.port = 5353;
+        assert_eq!(
+            config.server_addrs(),
+            vec!["[2001:4860:4860::8888]:5353".parse().unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_parse_resolv_conf() {
+        let content = "# generated\n\
+                       nameserver 10.0.0.1\n\
+                       nameserver fe80::1%eth0\n\
+                       nameserver 2001:db8::1\n\
+                       domain example.org\n\
+                       search corp.example.com example.com\n\
+                       options ndots:2 timeout:3 attempts:9 rotate use-vc edns0\n";
+        let config = ResolverConfig::parse_resolv_conf(content);
+        assert_eq!(
+            config.nameservers,
+            vec![
+                "10.0.0.1".parse::<IpAddr>().unwrap(),
+                "2001:db8::1".parse::<IpAddr>().unwrap()
+            ]
+        );
+        assert_eq!(config.search, vec!["corp.example.com", "example.com"]);
+        assert_eq!(config.ndots, 2);
+        assert_eq!(config.timeout, Some(Duration::from_secs(3)));
+        assert_eq!(config.attempts, 5);
+        assert!(config.rotate);
+        assert!(config.tcp);
+    }
+
+    #[test]
+    fn test_parse_resolv_conf_empty() {
+        let config = ResolverConfig::parse_resolv_conf("domain example.org\n");
+        assert_eq!(config.nameservers, vec![IpAddr::from([127, 0, 0, 1])]);
+        assert_eq!(config.search, vec!["example.org"]);
+        assert_eq!(config.ndots, 1);
+    }
+
+    #[test]
+    fn test_candidate_names() {
+        let mut config = ResolverConfig::new(IpAddr::from(DEFAULT_SERVER));
+        config.search = vec![String::from("a.com"), String::from("b.com.")];
+        assert_eq!(
+            config.candidate_names("www"),
+            vec!["www.a.com", "www.b.com", "www"]
+        );
+        assert_eq!(
+            config.candidate_names("www.c"),
+            vec!["www.c", "www.c.a.com", "www.c.b.com"]
+        );
+        assert_eq!(config.candidate_names("www."), vec!["www"]);
+    }
+
+    #[test]
+    fn test_search_list() {
+        let mut config = ResolverConfig::new(IpAddr::from(DEFAULT_SERVER));
+        config.search = vec![String::from("a.com"), String::from("b.com")];
+        // a result is NODATA if it is empty
+        let mut search = config.search_list("www", |records: &Vec<u8>| records.is_empty());
+        assert_eq!(search.next_name().unwrap(), "www.a.com");
+        assert!(search.record(Ok(vec![])).is_none());
+        assert_eq!(search.next_name().unwrap(), "www.b.com");
+        assert!(search
+            .record(Err(CustomError::Rcode(Rcode::NXDomain)))
+            .is_none());
+        assert_eq!(search.next_name().unwrap(), "www");
+        assert!(search.record(Ok(vec![])).is_none());
+        assert!(search.next_name().is_none());
+        // the first NODATA result is kept
+        assert_eq!(search.finish().unwrap(), vec![]);
+
+        let mut search = config.search_list("www", |records: &Vec<u8>| records.is_empty());
+        search.next_name();
+        assert_eq!(search.record(Ok(vec![1])).unwrap().unwrap(), vec![1]);
+        let mut search = config.search_list("www", |records: &Vec<u8>| records.is_empty());
+        search.next_name();
+        assert!(matches!(
+            search.record(Err(CustomError::Rcode(Rcode::ServFail))),
+            Some(Err(CustomError::Rcode(Rcode::ServFail)))
+        ));
+        let search = config.search_list("www", |records: &Vec<u8>| records.is_empty());
+        assert!(matches!(search.finish(), Err(CustomError::EmptyResponse)));
+    }
+}
@@ -0,0 +1,202 @@
+use crate::client::random_u64;
+use crate::customerror::CustomError;
+use crate::edns::{Edns, EdnsOption, DEFAULT_PAYLOAD};
+use crate::response::Message;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::vec::Vec;
+
+/// The option code of a DNS Cookie (RFC 7873)
+pub const OPTION_COOKIE: u16 = 10;
+
+/// The length of a client cookie
+const CLIENT_COOKIE_LENGTH: usize = 8;
+/// A server cookie has 8 to 32 bytes
+const SERVER_COOKIE_LENGTH: std::ops::RangeInclusive<usize> = 8..=32;
+
+/// The cookies used with a single name server
+#[derive(Debug, PartialEq, Clone)]
+struct Cookie {
+    client: [u8; CLIENT_COOKIE_LENGTH],
+    /// Empty until the server sent one
+    server: Vec<u8>,
+}
+
+/// Keeps the DNS Cookies (RFC 7873) of every name server between queries.
+/// Every server gets its own random client cookie, so servers can't track the client across each other.
+/// The server cookie of a reply is stored and sent with every later query to the same server.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct CookieJar {
+    cookies: HashMap<SocketAddr, Cookie>,
+}
+
+impl CookieJar {
+    /// Returns an empty CookieJar, client cookies are created when a server is first asked
+    pub fn new() -> Self {
+        CookieJar {
+            cookies: HashMap::new(),
+        }
+    }
+
+    /// Returns the COOKIE option for a server: the client cookie followed by the server cookie if one is known
+    ///
+    /// # Arguments
+    ///
+    /// * `server` - The socket adress of the name server
+    pub fn option(&mut self, server: &SocketAddr) -> EdnsOption {
+        let cookie = self.cookies.entry(*server).or_insert_with(|| Cookie {
+            client: random_u64().to_be_bytes(),
+            server: vec![],
+        });
+        EdnsOption::new(OPTION_COOKIE, [&cookie.client[..], &cookie.server].concat())
+    }
+
+    /// Returns the server cookie that was stored for a server
+    pub fn server_cookie(&self, server: &SocketAddr) -> Option<&[u8]> {
+        self.cookies
+            .get(server)
+            .map(|c| c.server.as_slice())
+            .filter(|s| !s.is_empty())
+    }
+
+    /// Returns the query with the COOKIE option for a server.
+    /// EDNS is added if the query has none, an earlier COOKIE option is replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The query containing Header and Question
+    /// * `server` - The socket adress of the name server
+    pub fn add_cookie(
+        &mut self,
+        message: &[u8],
+        server: &SocketAddr,
+    ) -> Result<Vec<u8>, CustomError> {
+        let query = Message::parse(message)?;
+        let mut edns = query.edns.unwrap_or_else(|| Edns::new(DEFAULT_PAYLOAD));
+        edns.options.retain(|o| o.code != OPTION_COOKIE);
+        edns.options.push(self.option(server));
+
+        let mut header = query.header;
+        header.arcount = 1;
+        let mut res = header.get_header();
+        for question in &query.questions {
+            res.extend(question.get_question());
+        }
+        res.extend(edns.to_wire());
+        Ok(res)
+    }
+
+    /// Stores the server cookie of a reply.
+    /// A reply without a COOKIE option is accepted, the server doesn't support cookies.
+    /// Returns an error if the client cookie of the reply isn't the one that was sent,
+    /// then the reply may be spoofed and has to be dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `server` - The socket adress of the name server
+    /// * `edns` - The OPT record of the reply
+    pub fn update(&mut self, server: &SocketAddr, edns: &Edns) -> Result<(), CustomError> {
+        let data = match edns.option(OPTION_COOKIE) {
+            Some(option) => &option.data,
+            None => return Ok(()),
+        };
+        let cookie = self
+            .cookies
+            .get_mut(server)
+            .ok_or(CustomError::ResponseError)?;
+        if data.len() < CLIENT_COOKIE_LENGTH || data[..CLIENT_COOKIE_LENGTH] != cookie.client {
+            return Err(CustomError::ResponseError);
+        }
+        let server_cookie = &data[CLIENT_COOKIE_LENGTH..];
+        if !SERVER_COOKIE_LENGTH.contains(&server_cookie.len()) {
+            return Err(CustomError::ResponseError);
+        }
+        cookie.server = server_cookie.to_vec();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::Header;
+    use crate::qtype::Qtype;
+    use crate::question::Question;
+
+    fn server() -> SocketAddr {
+        "192.0.2.53:53".parse().unwrap()
+    }
+
+    #[test]
+    fn test_option() {
+        let mut jar = CookieJar::new();
+        let first = jar.option(&server());
+        assert_eq!(first.code, OPTION_COOKIE);
+        assert_eq!(first.data.len(), 8);
+        // the client cookie stays the same for a server
+        assert_eq!(jar.option(&server()), first);
+        assert_eq!(jar.server_cookie(&server()), None);
+    }
+
+    #[test]
+    fn test_update() {
+        let mut jar = CookieJar::new();
+        let client = jar.option(&server()).data;
+        let reply = |server_cookie: &[u8]| {
+            Edns::new(1232).with_option(OPTION_COOKIE, [&client[..], server_cookie].concat())
+        };
+
+        assert!(jar.update(&server(), &Edns::new(1232)).is_ok());
+        assert!(jar.update(&server(), &reply(&[1; 4])).is_err());
+        assert!(jar.update(&server(), &reply(&[1; 33])).is_err());
+        assert!(jar.update(&server(), &reply(&[1; 16])).is_ok());
+        assert_eq!(jar.server_cookie(&server()), Some(&[1; 16][..]));
+        assert_eq!(jar.option(&server()).data, [&client[..], &[1; 16]].concat());
+
+        // a reply with another client cookie is spoofed
+        let spoofed = Edns::new(1232).with_option(OPTION_COOKIE, vec![0; 16]);
+        assert!(jar.update(&server(), &spoofed).is_err());
+        let other: SocketAddr = "192.0.2.54:53".parse().unwrap();
+        assert!(jar.update(&other, &reply(&[1; 16])).is_err());
+    }
+
+    #[test]
+    fn test_add_cookie() {
+        let mut jar = CookieJar::new();
+        let question = Question::new("example.com", Qtype::A)
+            .unwrap()
+            .get_question();
+        let message = [Header::new(7, false, 0).get_header(), question.clone()].concat();
+
+        let query = jar.add_cookie(&message, &server()).unwrap();
+        let client = jar.option(&server()).data;
+        let mut opt = vec![0, 0, 41, 4, 208, 0, 0, 0, 0, 0, 12, 0, 10, 0, 8];
+        opt.extend_from_slice(&client);
+        let expected = [
+            Header::new(7, false, 0)
+                .with_counts(1, 0, 0, 1)
+                .get_header(),
+            question,
+            opt,
+        ]
+        .concat();
+        assert_eq!(query, expected);
+
+        // the OPT record of the query is kept and an old cookie replaced
+        let edns = Edns::new(4096)
+            .with_do(true)
+            .with_option(OPTION_COOKIE, vec![9; 8]);
+        let message = [
+            &Header::new(7, false, 0)
+                .with_counts(1, 0, 0, 1)
+                .get_header()[..],
+            &message[12..],
+            &edns.to_wire(),
+        ]
+        .concat();
+        let query = Message::parse(&jar.add_cookie(&message, &server()).unwrap()).unwrap();
+        let edns = query.edns.unwrap();
+        assert_eq!((edns.payload, edns.dnssec_ok), (4096, true));
+        assert_eq!(edns.options, vec![EdnsOption::new(OPTION_COOKIE, client)]);
+    }
+}
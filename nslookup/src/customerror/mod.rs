@@ -1,3 +1,4 @@
+use crate::rcode::Rcode;
 use std::fmt;
 
 pub enum CustomError {
@@ -7,8 +8,76 @@ pub enum CustomError {
     IoError(std::io::Error),
     ResponseError,
     QtypeNotSupported(usize),
+    UnknownQtype(String),
+    UnknownClass(String),
     EmptyResponse,
+    NoData,
+    Rcode(Rcode),
     IpParseError,
+    NoNameserver,
+    Timeout(std::net::SocketAddr),
+    Malformed(usize, ParseReason),
+    InvalidName(String, NameReason),
+}
+
+/// Why a DNS-Package couldn't be parsed
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ParseReason {
+    /// The package ends before the field
+    Truncated,
+    /// A compression pointer doesn't point backwards
+    ForwardPointer,
+    /// A compression pointer leads to a pointer that was already followed
+    PointerLoop,
+    /// A domain uses more compression pointers than allowed
+    TooManyPointers,
+    /// A label starts with the reserved bits 01 or 10
+    InvalidLabelType,
+    /// A domain is longer than 255 bytes
+    NameTooLong,
+    /// The RDATA doesn't match the length or layout of its type
+    RdataLength,
+}
+
+/// Why a domain name is invalid
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum NameReason {
+    /// A label is empty, e.g. two dots in a row
+    EmptyLabel,
+    /// A label is longer than 63 bytes
+    LabelTooLong,
+    /// The domain is longer than 255 bytes on the wire
+    NameTooLong,
+    /// A backslash isn't followed by a character or three digits up to 255
+    InvalidEscape,
+    /// A label can't be converted with IDNA or punycode
+    InvalidIdna,
+}
+
+impl fmt::Display for NameReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            NameReason::EmptyLabel => write!(f, "empty label"),
+            NameReason::LabelTooLong => write!(f, "label is longer than 63 bytes"),
+            NameReason::NameTooLong => write!(f, "domain is longer than 255 bytes"),
+            NameReason::InvalidEscape => write!(f, "invalid escape sequence"),
+            NameReason::InvalidIdna => write!(f, "invalid internationalized label"),
+        }
+    }
+}
+
+impl fmt::Display for ParseReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            ParseReason::Truncated => write!(f, "package is truncated"),
+            ParseReason::ForwardPointer => write!(f, "compression pointer doesn't point backwards"),
+            ParseReason::PointerLoop => write!(f, "compression pointers loop"),
+            ParseReason::TooManyPointers => write!(f, "too many compression pointers"),
+            ParseReason::InvalidLabelType => write!(f, "invalid label type"),
+            ParseReason::NameTooLong => write!(f, "domain is longer than 255 bytes"),
+            ParseReason::RdataLength => write!(f, "record data has the wrong length"),
+        }
+    }
 }
 
 impl fmt::Display for CustomError {
@@ -22,8 +91,22 @@ impl fmt::Display for CustomError {
             }
             CustomError::ResponseError => write!(f, "Response ist faulty."),
             CustomError::QtypeNotSupported(ref x) => write!(f, "Qtype {} is not supported", x),
+            CustomError::UnknownQtype(ref x) => write!(f, "Unknown Qtype {}", x),
+            CustomError::UnknownClass(ref x) => write!(f, "Unknown Class {}", x),
             CustomError::EmptyResponse => write!(f, "Response is empty"),
+            CustomError::NoData => write!(f, "The name has no records of the type"),
+            CustomError::Rcode(ref x) => write!(f, "Server answered {}", x),
             CustomError::IpParseError => write!(f, "Invalid Ip Adress"),
+            CustomError::NoNameserver => write!(f, "No name server configured"),
+            CustomError::Timeout(ref server) => {
+                write!(f, "Connection timed out, no reply from {}", server)
+            }
+            CustomError::Malformed(ref offset, ref reason) => {
+                write!(f, "Malformed package at offset {}: {}", offset, reason)
+            }
+            CustomError::InvalidName(ref name, ref reason) => {
+                write!(f, "Invalid domain {}: {}", name, reason)
+            }
         }
     }
 }
@@ -39,8 +122,22 @@ impl fmt::Debug for CustomError {
             }
             CustomError::ResponseError => write!(f, "Response ist faulty."),
             CustomError::QtypeNotSupported(ref x) => write!(f, "Qtype {} is not supported", x),
+            CustomError::UnknownQtype(ref x) => write!(f, "Unknown Qtype {}", x),
+            CustomError::UnknownClass(ref x) => write!(f, "Unknown Class {}", x),
             CustomError::EmptyResponse => write!(f, "Response is empty"),
+            CustomError::NoData => write!(f, "The name has no records of the type"),
+            CustomError::Rcode(ref x) => write!(f, "Server answered {}", x),
             CustomError::IpParseError => write!(f, "Invalid Ip Adress"),
+            CustomError::NoNameserver => write!(f, "No name server configured"),
+            CustomError::Timeout(ref server) => {
+                write!(f, "Connection timed out, no reply from {}", server)
+            }
+            CustomError::Malformed(ref offset, ref reason) => {
+                write!(f, "Malformed package at offset {}: {}", offset, reason)
+            }
+            CustomError::InvalidName(ref name, ref reason) => {
+                write!(f, "Invalid domain {}: {}", name, reason)
+            }
         }
     }
 }
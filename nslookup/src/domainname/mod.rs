@@ -0,0 +1,260 @@
+use crate::customerror::{CustomError, NameReason};
+use std::fmt;
+use std::str::FromStr;
+use std::vec::Vec;
+
+/// The maximum length of a label
+pub const MAX_LABEL_LENGTH: usize = 63;
+/// The maximum length of a domain on the wire, including all length octets
+pub const MAX_NAME_LENGTH: usize = 255;
+
+/// A validated domain name.
+/// It is kept as labels, so a label may contain any byte, even a dot.
+/// No label is empty or longer than 63 bytes and the name isn't longer than 255 bytes on the wire.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct DomainName {
+    labels: Vec<Vec<u8>>,
+}
+
+impl DomainName {
+    /// Returns the root domain
+    pub fn root() -> Self {
+        DomainName { labels: vec![] }
+    }
+
+    /// Returns a DomainName of the given labels or an error if they are invalid
+    ///
+    /// # Arguments
+    ///
+    /// * `labels` - The labels without the root label
+    pub fn from_labels(labels: Vec<Vec<u8>>) -> Result<Self, CustomError> {
+        let name = DomainName { labels };
+        let invalid = |reason| Err(CustomError::InvalidName(name.to_string(), reason));
+        if name.labels.iter().any(|l| l.is_empty()) {
+            return invalid(NameReason::EmptyLabel);
+        }
+        if name.labels.iter().any(|l| l.len() > MAX_LABEL_LENGTH) {
+            return invalid(NameReason::LabelTooLong);
+        }
+        if name.wire_length() > MAX_NAME_LENGTH {
+            return invalid(NameReason::NameTooLong);
+        }
+        Ok(name)
+    }
+
+    /// Returns the labels without the root label
+    pub fn labels(&self) -> &[Vec<u8>] {
+        &self.labels
+    }
+
+    /// Returns true if this is the root domain
+    pub fn is_root(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Returns true if both names are the same, the case of ASCII letters may differ (RFC 4343)
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The other name
+    pub fn eq_ignore_ascii_case(&self, other: &DomainName) -> bool {
+        self.labels.len() == other.labels.len()
+            && self
+                .labels
+                .iter()
+                .zip(&other.labels)
+                .all(|(a, b)| a.eq_ignore_ascii_case(b))
+    }
+
+    /// Returns the length on the wire, including the length octets and the root label
+    pub fn wire_length(&self) -> usize {
+        self.labels.iter().map(|l| l.len() + 1).sum::<usize>() + 1
+    }
+
+    /// Returns the uncompressed wire format: every label with its length, then a zero
+    ///
+    /// Example: example.com -> 7 e x a m p l e 3 c o m 0
+    pub fn to_wire(&self) -> Vec<u8> {
+        let mut wire = Vec::with_capacity(self.wire_length());
+        for label in &self.labels {
+            wire.push(label.len() as u8);
+            wire.extend_from_slice(label);
+        }
+        wire.push(0);
+        wire
+    }
+}
+
+impl fmt::Display for DomainName {
+    /// Writes the name in presentation format without the trailing dot, the root domain is "."
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        if self.is_root() {
+            write!(f, ".")
+        } else {
+            let labels = self
+                .labels
+                .iter()
+                .map(|l| escape_label(l))
+                .collect::<Vec<_>>();
+            write!(f, "{}", labels.join("."))
+        }
+    }
+}
+
+impl FromStr for DomainName {
+    type Err = CustomError;
+
+    /// Parses a name in presentation format.
+    /// A single trailing dot is allowed, "." and "" are the root domain.
+    /// A backslash followed by three digits is the byte with this value,
+    /// a backslash followed by any other character is that character, e.g. "\." for a dot inside a label.
+    ///
+    /// # Arguments
+    /// `s` - the name
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DomainName::from_labels(split_labels(s)?).map_err(|e| match e {
+            CustomError::InvalidName(_, reason) => {
+                CustomError::InvalidName(String::from(s), reason)
+            }
+            e => e,
+        })
+    }
+}
+
+/// Splits a name in presentation format into its unescaped labels without checking their lengths.
+/// An empty label or an invalid escape sequence is an error, "." and "" are the root domain.
+///
+/// # Arguments
+///
+/// * `s` - the name
+pub(crate) fn split_labels(s: &str) -> Result<Vec<Vec<u8>>, CustomError> {
+    if s == "." || s.is_empty() {
+        return Ok(vec![]);
+    }
+    let invalid = |reason| CustomError::InvalidName(String::from(s), reason);
+
+    let mut labels = vec![];
+    let mut label = vec![];
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'.' => {
+                if label.is_empty() {
+                    return Err(invalid(NameReason::EmptyLabel));
+                }
+                labels.push(label);
+                label = vec![];
+            }
+            b'\\' => match bytes.next() {
+                Some(d) if d.is_ascii_digit() => {
+                    let digits = [Some(d), bytes.next(), bytes.next()];
+                    let mut value = 0u16;
+                    for digit in digits.iter() {
+                        match digit {
+                            Some(d) if d.is_ascii_digit() => {
+                                value = value * 10 + u16::from(d - b'0')
+                            }
+                            _ => return Err(invalid(NameReason::InvalidEscape)),
+                        }
+                    }
+                    if value > 255 {
+                        return Err(invalid(NameReason::InvalidEscape));
+                    }
+                    label.push(value as u8);
+                }
+                Some(c) => label.push(c),
+                None => return Err(invalid(NameReason::InvalidEscape)),
+            },
+            _ => label.push(b),
+        }
+    }
+    // the name didn't end with a dot
+    if !label.is_empty() {
+        labels.push(label);
+    }
+    Ok(labels)
+}
+
+/// Escapes a label for the presentation format the way dig prints it.
+/// Dots and backslashes get a leading backslash,
+/// bytes that aren't printable ASCII are written as backslash followed by three decimal digits.
+///
+/// Example: a.b -> a\.b
+///
+/// # Arguments
+///
+/// * `label` - The bytes of the label
+pub fn escape_label(label: &[u8]) -> String {
+    let mut escaped = String::with_capacity(label.len());
+    for &b in label {
+        match b {
+            b'.' | b'\\' => {
+                escaped.push('\\');
+                escaped.push(b as char);
+            }
+            0x21..=0x7e => escaped.push(b as char),
+            _ => escaped.push_str(&format!("\\{:03}", b)),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        let name = "www.example.com.".parse::<DomainName>().unwrap();
+        assert_eq!(name.labels().len(), 3);
+        assert_eq!(name.to_string(), "www.example.com");
+        assert!(".".parse::<DomainName>().unwrap().is_root());
+        assert_eq!(DomainName::root().to_string(), ".");
+        let upper = "WWW.Example.COM".parse::<DomainName>().unwrap();
+        assert!(name.eq_ignore_ascii_case(&upper));
+        assert!(!name.eq_ignore_ascii_case(&"www.example".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_escapes() {
+        let name = r"a\.b.c\032d\\".parse::<DomainName>().unwrap();
+        assert_eq!(name.labels(), &[b"a.b".to_vec(), b"c d\\".to_vec()][..]);
+        assert_eq!(name.to_string(), r"a\.b.c\032d\\");
+        assert_eq!(
+            r"\255".parse::<DomainName>().unwrap().labels(),
+            &[vec![255]][..]
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let reason = |s: &str| match s.parse::<DomainName>() {
+            Err(CustomError::InvalidName(_, reason)) => Some(reason),
+            _ => None,
+        };
+        assert_eq!(reason("a..b"), Some(NameReason::EmptyLabel));
+        assert_eq!(reason(".a"), Some(NameReason::EmptyLabel));
+        assert_eq!(reason("a.."), Some(NameReason::EmptyLabel));
+        assert_eq!(reason(&"a".repeat(64)), Some(NameReason::LabelTooLong));
+        assert_eq!(reason(&"a".repeat(63)), None);
+        let long = vec!["a".repeat(63); 4].join(".");
+        assert_eq!(reason(&long), Some(NameReason::NameTooLong));
+        assert_eq!(reason(&long[2..]), None);
+        assert_eq!(reason(r"a\256"), Some(NameReason::InvalidEscape));
+        assert_eq!(reason(r"a\25"), Some(NameReason::InvalidEscape));
+        assert_eq!(reason(r"a\"), Some(NameReason::InvalidEscape));
+    }
+
+    #[test]
+    fn test_to_wire() {
+        assert_eq!(
+            "example.com".parse::<DomainName>().unwrap().to_wire(),
+            b"\x07example\x03com\x00"
+        );
+        assert_eq!(DomainName::root().to_wire(), vec![0]);
+        assert_eq!(
+            r"a\.b".parse::<DomainName>().unwrap().to_wire(),
+            b"\x03a.b\x00"
+        );
+    }
+}
@@ -0,0 +1,537 @@
+use crate::customerror::{CustomError, ParseReason};
+use crate::qtype::Qtype;
+use crate::record::{RData, Record};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+use std::vec::Vec;
+
+/// The UDP payload size that is advertised if no other is given.
+/// It fits into a single IPv6 packet on almost every link (DNS flag day 2020).
+pub const DEFAULT_PAYLOAD: u16 = 1232;
+
+/// The option code of EDNS Client Subnet (RFC 7871)
+pub const OPTION_CLIENT_SUBNET: u16 = 8;
+
+/// A single option of the OPT record, kept as its code and raw data
+#[derive(Debug, PartialEq, Clone)]
+pub struct EdnsOption {
+    pub code: u16,
+    pub data: Vec<u8>,
+}
+
+impl EdnsOption {
+    /// Returns a new EdnsOption
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - The option code, e.g. 10 for a cookie
+    /// * `data` - The data of the option
+    pub fn new(code: u16, data: Vec<u8>) -> Self {
+        EdnsOption { code, data }
+    }
+}
+
+impl fmt::Display for EdnsOption {
+    /// Writes the option like dig does, e.g. "NSID: 6e7331" or "OPT=65001: 0102"
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let hex = self
+            .data
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        if self.code == OPTION_CLIENT_SUBNET {
+            if let Ok(subnet) = ClientSubnet::from_option(self) {
+                return write!(f, "CLIENT-SUBNET: {}", subnet);
+            }
+        }
+        match option_name(self.code) {
+            Some(name) => write!(f, "{}: {}", name, hex),
+            None => write!(f, "OPT={}: {}", self.code, hex),
+        }
+    }
+}
+
+/// The EDNS(0) pseudo-record OPT (RFC 6891).
+/// It is sent in the additional section and tells the other side
+/// how large UDP replies may be and which extensions are understood.
+///
+///+------------+--------------+------------------------------+
+///| NAME       | domain name  | always the root domain       |
+///| TYPE       | u_int16_t    | OPT (41)                     |
+///| CLASS      | u_int16_t    | UDP payload size             |
+///| TTL        | u_int32_t    | extended RCODE, VERSION, DO  |
+///| RDLEN      | u_int16_t    | length of all options        |
+///| RDATA      | octet stream | {code, length, data} pairs   |
+///+------------+--------------+------------------------------+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Edns {
+    /// The largest UDP payload the sender can receive
+    pub payload: u16,
+    /// The upper 8 bits of the Rcode
+    pub extended_rcode: u8,
+    /// The EDNS version, only 0 is defined
+    pub version: u8,
+    /// DNSSEC OK, the sender wants DNSSEC records
+    pub dnssec_ok: bool,
+    pub options: Vec<EdnsOption>,
+}
+
+impl Edns {
+    /// Returns a new Edns of version 0 without flags and options
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The largest UDP payload that can be received
+    pub fn new(payload: u16) -> Self {
+        Edns {
+            payload,
+            extended_rcode: 0,
+            version: 0,
+            dnssec_ok: false,
+            options: vec![],
+        }
+    }
+
+    /// Returns the Edns with the DNSSEC OK flag set or cleared
+    pub fn with_do(mut self, dnssec_ok: bool) -> Self {
+        self.dnssec_ok = dnssec_ok;
+        self
+    }
+
+    /// Returns the Edns with another version than 0
+    pub fn with_version(mut self, version: u8) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Returns the Edns with another option appended
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - The option code
+    /// * `data` - The data of the option
+    pub fn with_option(mut self, code: u16, data: Vec<u8>) -> Self {
+        self.options.push(EdnsOption::new(code, data));
+        self
+    }
+
+    /// Returns the Edns with a Client Subnet option, an earlier one is replaced
+    pub fn with_client_subnet(mut self, subnet: &ClientSubnet) -> Self {
+        self.options.retain(|o| o.code != OPTION_CLIENT_SUBNET);
+        self.options.push(subnet.to_option());
+        self
+    }
+
+    /// Returns the first option with the given code
+    pub fn option(&self, code: u16) -> Option<&EdnsOption> {
+        self.options.iter().find(|o| o.code == code)
+    }
+
+    /// Returns the Client Subnet option, None if there is none or it is malformed
+    pub fn client_subnet(&self) -> Option<ClientSubnet> {
+        self.option(OPTION_CLIENT_SUBNET)
+            .and_then(|o| ClientSubnet::from_option(o).ok())
+    }
+
+    /// Returns the OPT record in wire format, ready to be appended to the additional section
+    pub fn to_wire(&self) -> Vec<u8> {
+        let rdata = self
+            .options
+            .iter()
+            .flat_map(|o| {
+                [
+                    &o.code.to_be_bytes()[..],
+                    &(o.data.len() as u16).to_be_bytes(),
+                    &o.data,
+                ]
+                .concat()
+            })
+            .collect::<Vec<u8>>();
+        let ttl = u32::from(self.extended_rcode) << 24
+            | u32::from(self.version) << 16
+            | (self.dnssec_ok as u32) << 15;
+
+        let mut wire = vec![0];
+        wire.extend_from_slice(&Qtype::OPT.value().to_be_bytes());
+        wire.extend_from_slice(&self.payload.to_be_bytes());
+        wire.extend_from_slice(&ttl.to_be_bytes());
+        wire.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        wire.extend_from_slice(&rdata);
+        wire
+    }
+
+    /// Reads the Edns from a parsed OPT record.
+    /// The offset of an error is counted from the beginning of the RDATA.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - The OPT record of the additional section
+    pub fn from_record(record: &Record) -> Result<Self, CustomError> {
+        let rdata = match (&record.rtype, &record.rdata) {
+            (Qtype::OPT, RData::Unknown(rdata)) => rdata,
+            _ => return Err(CustomError::ResponseError),
+        };
+
+        Ok(Edns {
+            payload: record.class.value(),
+            extended_rcode: (record.ttl >> 24) as u8,
+            version: (record.ttl >> 16) as u8,
+            dnssec_ok: record.ttl & 0x8000 != 0,
+            options: get_options(rdata)?,
+        })
+    }
+}
+
+/// Reads the options of the RDATA of an OPT record.
+/// Every option is a code and a length of two bytes each, followed by the data.
+/// The offset of an error is counted from the beginning of the RDATA.
+///
+/// # Arguments
+///
+/// * `rdata` - The RDATA of the OPT record
+pub(crate) fn get_options(rdata: &[u8]) -> Result<Vec<EdnsOption>, CustomError> {
+    let mut options = vec![];
+    let mut index = 0;
+    while index < rdata.len() {
+        if index + 4 > rdata.len() {
+            return Err(CustomError::Malformed(index, ParseReason::RdataLength));
+        }
+        let code = u16::from_be_bytes([rdata[index], rdata[index + 1]]);
+        let length = u16::from_be_bytes([rdata[index + 2], rdata[index + 3]]) as usize;
+        let data = rdata
+            .get(index + 4..index + 4 + length)
+            .ok_or(CustomError::Malformed(index + 2, ParseReason::RdataLength))?;
+        options.push(EdnsOption::new(code, data.to_vec()));
+        index += 4 + length;
+    }
+    Ok(options)
+}
+
+impl fmt::Display for Edns {
+    /// Writes the OPT record like the OPT PSEUDOSECTION of dig
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            ";; OPT PSEUDOSECTION:\n; EDNS: version: {}, flags:{}; udp: {}",
+            self.version,
+            if self.dnssec_ok { " do" } else { "" },
+            self.payload
+        )?;
+        for option in &self.options {
+            write!(f, "\n; {}", option)?;
+        }
+        Ok(())
+    }
+}
+
+/// The EDNS Client Subnet option (RFC 7871).
+/// A resolver tells the server which network the client is in, so geo-DNS can answer for that location.
+/// The server returns the scope prefix, the length of the network the answer is valid for.
+///
+///+------------------+-------------+---------------------------------------+
+///| FAMILY           | u_int16_t   | 1 IPv4, 2 IPv6                        |
+///| SOURCE PREFIX    | u_int8_t    | significant bits of the address       |
+///| SCOPE PREFIX     | u_int8_t    | 0 in queries, set by the server       |
+///| ADDRESS          | octets      | only the bytes the source prefix uses |
+///+------------------+-------------+---------------------------------------+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ClientSubnet {
+    /// The network, all bits after the source prefix are zero
+    pub address: IpAddr,
+    pub source_prefix: u8,
+    pub scope_prefix: u8,
+}
+
+impl ClientSubnet {
+    /// Returns a new ClientSubnet with scope prefix 0 like it is sent in a query.
+    /// The bits of the address after the prefix are cleared.
+    /// Returns an error if the prefix is longer than the address.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The IPv4 or IPv6 adress of the client or its network
+    /// * `source_prefix` - The number of significant bits
+    pub fn new(address: IpAddr, source_prefix: u8) -> Result<Self, CustomError> {
+        if source_prefix > max_prefix(&address) {
+            return Err(CustomError::IpParseError);
+        }
+        Ok(ClientSubnet {
+            address: mask(address, source_prefix),
+            source_prefix,
+            scope_prefix: 0,
+        })
+    }
+
+    /// Returns the option in the layout of RFC 7871 6.
+    /// The address is cut after the last byte the source prefix uses.
+    pub fn to_option(&self) -> EdnsOption {
+        let (family, octets) = match self.address {
+            IpAddr::V4(ip) => (1u16, ip.octets().to_vec()),
+            IpAddr::V6(ip) => (2u16, ip.octets().to_vec()),
+        };
+        let mut data = family.to_be_bytes().to_vec();
+        data.push(self.source_prefix);
+        data.push(self.scope_prefix);
+        data.extend_from_slice(&octets[..prefix_bytes(self.source_prefix)]);
+        EdnsOption::new(OPTION_CLIENT_SUBNET, data)
+    }
+
+    /// Reads a ClientSubnet from an option of a reply.
+    /// The offset of an error is counted from the beginning of the option data.
+    ///
+    /// # Arguments
+    ///
+    /// * `option` - The option with code 8
+    pub fn from_option(option: &EdnsOption) -> Result<Self, CustomError> {
+        let data = &option.data;
+        if option.code != OPTION_CLIENT_SUBNET {
+            return Err(CustomError::ResponseError);
+        }
+        if data.len() < 4 {
+            return Err(CustomError::Malformed(0, ParseReason::RdataLength));
+        }
+        let source_prefix = data[2];
+        let scope_prefix = data[3];
+        let address = &data[4..];
+        if address.len() != prefix_bytes(source_prefix) {
+            return Err(CustomError::Malformed(4, ParseReason::RdataLength));
+        }
+
+        let address = match u16::from_be_bytes([data[0], data[1]]) {
+            1 if source_prefix <= 32 && scope_prefix <= 32 => {
+                let mut octets = [0u8; 4];
+                octets[..address.len()].copy_from_slice(address);
+                IpAddr::from(octets)
+            }
+            2 if source_prefix <= 128 && scope_prefix <= 128 => {
+                let mut octets = [0u8; 16];
+                octets[..address.len()].copy_from_slice(address);
+                IpAddr::from(octets)
+            }
+            _ => return Err(CustomError::Malformed(0, ParseReason::RdataLength)),
+        };
+        Ok(ClientSubnet {
+            address: mask(address, source_prefix),
+            source_prefix,
+            scope_prefix,
+        })
+    }
+}
+
+impl fmt::Display for ClientSubnet {
+    /// Writes the subnet like dig does: address/source prefix/scope prefix
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{}/{}/{}",
+            self.address, self.source_prefix, self.scope_prefix
+        )
+    }
+}
+
+impl FromStr for ClientSubnet {
+    type Err = CustomError;
+
+    /// Parses a subnet in CIDR notation, e.g. "203.0.113.0/24" or "2001:db8::/56".
+    /// Without a prefix the whole address is used.
+    ///
+    /// # Arguments
+    /// `s` - the subnet
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, '/');
+        let address = parts
+            .next()
+            .unwrap_or("")
+            .parse::<IpAddr>()
+            .map_err(|_| CustomError::IpParseError)?;
+        let prefix = match parts.next() {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .map_err(|_| CustomError::IpParseError)?,
+            None => max_prefix(&address),
+        };
+        ClientSubnet::new(address, prefix)
+    }
+}
+
+/// Returns the number of bits of an address
+fn max_prefix(address: &IpAddr) -> u8 {
+    match address {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+/// Returns the number of address bytes a prefix uses
+fn prefix_bytes(prefix: u8) -> usize {
+    usize::from(prefix).div_ceil(8)
+}
+
+/// Clears the bits of an address after the prefix
+///
+/// # Arguments
+///
+/// * `address` - The IPv4 or IPv6 adress
+/// * `prefix` - The number of bits that are kept, at most the length of the address
+fn mask(address: IpAddr, prefix: u8) -> IpAddr {
+    match address {
+        IpAddr::V4(ip) => {
+            let bits = u32::from(ip) & u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+            IpAddr::V4(Ipv4Addr::from(bits))
+        }
+        IpAddr::V6(ip) => {
+            let bits = u128::from(ip) & u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+            IpAddr::V6(Ipv6Addr::from(bits))
+        }
+    }
+}
+
+/// Returns the name of an option code the way dig prints it, None if it has no name
+///
+/// # Arguments
+///
+/// * `code` - The option code
+fn option_name(code: u16) -> Option<&'static str> {
+    match code {
+        3 => Some("NSID"),
+        8 => Some("CLIENT-SUBNET"),
+        9 => Some("EXPIRE"),
+        10 => Some("COOKIE"),
+        11 => Some("KEEPALIVE"),
+        12 => Some("PADDING"),
+        15 => Some("EDE"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class::Class;
+    use crate::domainname::DomainName;
+
+    #[test]
+    fn test_to_wire() {
+        assert_eq!(
+            Edns::new(1232).to_wire(),
+            vec![0, 0, 41, 4, 208, 0, 0, 0, 0, 0, 0]
+        );
+        let edns = Edns::new(4096)
+            .with_do(true)
+            .with_version(1)
+            .with_option(3, vec![]);
+        assert_eq!(
+            edns.to_wire(),
+            vec![0, 0, 41, 16, 0, 0, 1, 128, 0, 0, 4, 0, 3, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_from_record() {
+        let record = Record::new(
+            DomainName::root(),
+            Qtype::OPT,
+            Class::get_class(1232),
+            0x0100_8000,
+            RData::Unknown(vec![0, 10, 0, 2, 0xab, 0xcd, 0, 3, 0, 0]),
+        );
+        let edns = Edns::from_record(&record).unwrap();
+        assert_eq!(edns.payload, 1232);
+        assert_eq!(edns.extended_rcode, 1);
+        assert_eq!(edns.version, 0);
+        assert!(edns.dnssec_ok);
+        assert_eq!(
+            edns.option(10),
+            Some(&EdnsOption::new(10, vec![0xab, 0xcd]))
+        );
+        assert_eq!(
+            edns.to_string(),
+            ";; OPT PSEUDOSECTION:\n; EDNS: version: 0, flags: do; udp: 1232\n; COOKIE: abcd\n; NSID: "
+        );
+    }
+
+    #[test]
+    fn test_client_subnet_v4() {
+        let subnet = "203.0.113.77/24".parse::<ClientSubnet>().unwrap();
+        assert_eq!(subnet.address, "203.0.113.0".parse::<IpAddr>().unwrap());
+        let edns = Edns::new(1232).with_client_subnet(&subnet);
+        assert_eq!(
+            edns.to_wire()[9..],
+            [0, 11, 0, 8, 0, 7, 0, 1, 24, 0, 203, 0, 113]
+        );
+
+        // reply of a CDN with scope /20
+        let option = EdnsOption::new(8, vec![0, 1, 24, 20, 203, 0, 113]);
+        let reply = ClientSubnet::from_option(&option).unwrap();
+        assert_eq!(reply.source_prefix, 24);
+        assert_eq!(reply.scope_prefix, 20);
+        assert_eq!(reply.to_string(), "203.0.113.0/24/20");
+        assert_eq!(option.to_string(), "CLIENT-SUBNET: 203.0.113.0/24/20");
+    }
+
+    #[test]
+    fn test_client_subnet_v6() {
+        let subnet = "2001:db8:1:2::/56".parse::<ClientSubnet>().unwrap();
+        assert_eq!(
+            subnet.to_option().data,
+            vec![0, 2, 56, 0, 0x20, 0x01, 0x0d, 0xb8, 0, 1, 0]
+        );
+
+        let option = EdnsOption::new(8, vec![0, 2, 56, 48, 0x20, 0x01, 0x0d, 0xb8, 0, 1, 0]);
+        let reply = ClientSubnet::from_option(&option).unwrap();
+        assert_eq!(reply.address, "2001:db8:1::".parse::<IpAddr>().unwrap());
+        assert_eq!(reply.scope_prefix, 48);
+
+        let host = "2001:db8::1".parse::<ClientSubnet>().unwrap();
+        assert_eq!(host.source_prefix, 128);
+        assert_eq!(host.to_option().data.len(), 20);
+        assert_eq!(
+            "0.0.0.0/0"
+                .parse::<ClientSubnet>()
+                .unwrap()
+                .to_option()
+                .data,
+            vec![0, 1, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_client_subnet_invalid() {
+        assert!("203.0.113.0/33".parse::<ClientSubnet>().is_err());
+        assert!("2001:db8::/129".parse::<ClientSubnet>().is_err());
+        assert!("203.0.113/24".parse::<ClientSubnet>().is_err());
+        let subnet = |data| ClientSubnet::from_option(&EdnsOption::new(8, data));
+        assert!(matches!(
+            subnet(vec![0, 1, 24]),
+            Err(CustomError::Malformed(0, ParseReason::RdataLength))
+        ));
+        assert!(matches!(
+            subnet(vec![0, 1, 24, 0, 203, 0]),
+            Err(CustomError::Malformed(4, ParseReason::RdataLength))
+        ));
+        assert!(subnet(vec![0, 3, 0, 0]).is_err());
+        assert!(subnet(vec![0, 1, 40, 0, 1, 2, 3, 4, 5]).is_err());
+    }
+
+    #[test]
+    fn test_from_record_malformed() {
+        let record = |rdata| {
+            Record::new(
+                DomainName::root(),
+                Qtype::OPT,
+                Class::IN,
+                0,
+                RData::Unknown(rdata),
+            )
+        };
+        assert!(matches!(
+            Edns::from_record(&record(vec![0, 10, 0])),
+            Err(CustomError::Malformed(0, ParseReason::RdataLength))
+        ));
+        assert!(matches!(
+            Edns::from_record(&record(vec![0, 10, 0, 3, 1, 2])),
+            Err(CustomError::Malformed(2, ParseReason::RdataLength))
+        ));
+    }
+}
@@ -0,0 +1,288 @@
+use crate::customerror::{CustomError, ParseReason};
+use crate::rcode::Rcode;
+use std::fmt;
+use std::vec::Vec;
+
+/// The header of a DNS-Message, used to build queries and to parse responses.
+/// The flags can be changed with the with_ methods, e.g. to turn off recursion.
+///
+///+++++++++flag1++++++++++++++++++++flag2+++++++++++++
+/// 7  6  5  4  3  2  1     0  7  6  5  4  3  2  1  0
+///+--+--+--+--+--+--+--+  +--+--+--+--+--+--+--+--+--+
+///|QR|   Opcode  |AA|TC|  |RD|RA| Z|AD|CD|   RCODE   |
+#[derive(Debug, PartialEq, Clone)]
+pub struct Header {
+    pub id: u16,
+    /// response (true) or query (false)
+    pub qr: bool,
+    /// kind of query, 0 is a standard query
+    pub opcode: u8,
+    /// authoritative answer
+    pub aa: bool,
+    /// truncated
+    pub tc: bool,
+    /// recursion desired
+    pub rd: bool,
+    /// recursion available
+    pub ra: bool,
+    /// reserved, must be zero
+    pub z: bool,
+    /// authentic data
+    pub ad: bool,
+    /// checking disabled
+    pub cd: bool,
+    /// the lower 4 bits of the Rcode
+    pub rcode: u8,
+    pub qdcount: u16,
+    pub ancount: u16,
+    pub nscount: u16,
+    pub arcount: u16,
+}
+
+impl Header {
+    /// Returns a new Header for a message with one question.
+    /// Recursion desired is set, all other flags are not.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - arbitrary 16 bit identifier
+    /// * `qr` - specify if query or response
+    /// * `opcode` - kind of query, 0 (standard query) to 15
+    pub fn new(id: u16, qr: bool, opcode: u8) -> Self {
+        Header {
+            id,
+            qr,
+            opcode: opcode & 0x0f,
+            aa: false,
+            tc: false,
+            rd: true,
+            ra: false,
+            z: false,
+            ad: false,
+            cd: false,
+            rcode: 0,
+            qdcount: 1,
+            ancount: 0,
+            nscount: 0,
+            arcount: 0,
+        }
+    }
+
+    /// Returns the Header with the authoritative answer flag set or cleared
+    pub fn with_aa(mut self, aa: bool) -> Self {
+        self.aa = aa;
+        self
+    }
+
+    /// Returns the Header with the truncated flag set or cleared
+    pub fn with_tc(mut self, tc: bool) -> Self {
+        self.tc = tc;
+        self
+    }
+
+    /// Returns the Header with the recursion desired flag set or cleared
+    pub fn with_rd(mut self, rd: bool) -> Self {
+        self.rd = rd;
+        self
+    }
+
+    /// Returns the Header with the recursion available flag set or cleared
+    pub fn with_ra(mut self, ra: bool) -> Self {
+        self.ra = ra;
+        self
+    }
+
+    /// Returns the Header with the reserved Z bit set or cleared
+    pub fn with_z(mut self, z: bool) -> Self {
+        self.z = z;
+        self
+    }
+
+    /// Returns the Header with the authentic data flag set or cleared
+    pub fn with_ad(mut self, ad: bool) -> Self {
+        self.ad = ad;
+        self
+    }
+
+    /// Returns the Header with the checking disabled flag set or cleared
+    pub fn with_cd(mut self, cd: bool) -> Self {
+        self.cd = cd;
+        self
+    }
+
+    /// Returns the Header with the lower 4 bits of the Rcode
+    pub fn with_rcode(mut self, rcode: Rcode) -> Self {
+        self.rcode = (rcode.value() & 0x0f) as u8;
+        self
+    }
+
+    /// Returns the Header with the number of entries in each section
+    ///
+    /// # Arguments
+    ///
+    /// * `qdcount` - The number of questions
+    /// * `ancount` - The number of answers
+    /// * `nscount` - The number of authority records
+    /// * `arcount` - The number of additional records
+    pub fn with_counts(mut self, qdcount: u16, ancount: u16, nscount: u16, arcount: u16) -> Self {
+        self.qdcount = qdcount;
+        self.ancount = ancount;
+        self.nscount = nscount;
+        self.arcount = arcount;
+        self
+    }
+
+    /// Parses the Header in an u8 Vector
+    pub fn get_header(&self) -> Vec<u8> {
+        let flags1 = (self.qr as u8) << 7
+            | (self.opcode & 0x0f) << 3
+            | (self.aa as u8) << 2
+            | (self.tc as u8) << 1
+            | self.rd as u8;
+        let flags2 = (self.ra as u8) << 7
+            | (self.z as u8) << 6
+            | (self.ad as u8) << 5
+            | (self.cd as u8) << 4
+            | self.rcode & 0x0f;
+
+        let mut header = Vec::with_capacity(12);
+        header.extend_from_slice(&self.id.to_be_bytes());
+        header.push(flags1);
+        header.push(flags2);
+        for count in &[self.qdcount, self.ancount, self.nscount, self.arcount] {
+            header.extend_from_slice(&count.to_be_bytes());
+        }
+        header
+    }
+
+    /// Parses the first 12 bytes of a DNS-Message
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The DNS-Message
+    pub fn parse(buf: &[u8]) -> Result<Self, CustomError> {
+        if buf.len() < 12 {
+            return Err(CustomError::Malformed(buf.len(), ParseReason::Truncated));
+        }
+        let count = |i: usize| u16::from_be_bytes([buf[i], buf[i + 1]]);
+        Ok(Header {
+            id: count(0),
+            qr: buf[2] & 0x80 != 0,
+            opcode: (buf[2] >> 3) & 0x0f,
+            aa: buf[2] & 0x04 != 0,
+            tc: buf[2] & 0x02 != 0,
+            rd: buf[2] & 0x01 != 0,
+            ra: buf[3] & 0x80 != 0,
+            z: buf[3] & 0x40 != 0,
+            ad: buf[3] & 0x20 != 0,
+            cd: buf[3] & 0x10 != 0,
+            rcode: buf[3] & 0x0f,
+            qdcount: count(4),
+            ancount: count(6),
+            nscount: count(8),
+            arcount: count(10),
+        })
+    }
+}
+
+impl fmt::Display for Header {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let flags = [
+            (self.qr, "qr"),
+            (self.aa, "aa"),
+            (self.tc, "tc"),
+            (self.rd, "rd"),
+            (self.ra, "ra"),
+            (self.z, "z"),
+            (self.ad, "ad"),
+            (self.cd, "cd"),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>();
+        write!(
+            f,
+            ";; opcode: {}, status: {}, id: {}\n;; flags: {}; QUERY: {}, ANSWER: {}, AUTHORITY: {}, ADDITIONAL: {}",
+            opcode_name(self.opcode),
+            Rcode::from_parts(self.rcode, 0),
+            self.id,
+            flags.join(" "),
+            self.qdcount,
+            self.ancount,
+            self.nscount,
+            self.arcount
+        )
+    }
+}
+
+/// Returns the mnemonic of an opcode or its number if it has none
+///
+/// # Arguments
+///
+/// * `opcode` - The opcode
+fn opcode_name(opcode: u8) -> String {
+    match opcode {
+        0 => String::from("QUERY"),
+        1 => String::from("IQUERY"),
+        2 => String::from("STATUS"),
+        4 => String::from("NOTIFY"),
+        5 => String::from("UPDATE"),
+        6 => String::from("DSO"),
+        x => x.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_header() {
+        assert_eq!(
+            Header::new(0xabcd, false, 0).get_header(),
+            vec![0xab, 0xcd, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0]
+        );
+        let header = Header::new(2, false, 0).with_rd(false).with_cd(true);
+        assert_eq!(header.get_header()[2..4], [0, 0x10]);
+        let header = Header::new(2, true, 5)
+            .with_aa(true)
+            .with_ra(true)
+            .with_ad(true)
+            .with_rcode(Rcode::Refused)
+            .with_counts(1, 2, 3, 4);
+        assert_eq!(
+            header.get_header(),
+            vec![0, 2, 0xad, 0xa5, 0, 1, 0, 2, 0, 3, 0, 4]
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let header = Header::parse(&[0, 2, 0x2d, 0x33, 0, 1, 0, 2, 0, 3, 0, 4]).unwrap();
+        assert_eq!(header.opcode, 5);
+        assert!(!header.qr && header.aa && !header.tc && header.rd);
+        assert!(!header.ra && !header.z && header.ad && header.cd);
+        assert_eq!(header.rcode, 3);
+        assert_eq!(
+            (
+                header.qdcount,
+                header.ancount,
+                header.nscount,
+                header.arcount
+            ),
+            (1, 2, 3, 4)
+        );
+        assert!(Header::parse(&[0, 2, 129]).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let header = Header::new(0x1234, false, 4)
+            .with_tc(true)
+            .with_z(true)
+            .with_cd(true)
+            .with_counts(1, 0, 0, 1);
+        assert_eq!(Header::parse(&header.get_header()).unwrap(), header);
+    }
+}
@@ -0,0 +1,313 @@
+use crate::customerror::{CustomError, NameReason};
+use crate::domainname::{split_labels, DomainName};
+use ::idna::uts46::{AsciiDenyList, DnsLength, Hyphens, Uts46};
+use std::str;
+use std::vec::Vec;
+
+/// The prefix of a label that is encoded with punycode
+const ACE_PREFIX: &str = "xn--";
+
+// Parameters of punycode (RFC 3492 5.)
+const BASE: u32 = 36;
+const TMIN: u32 = 1;
+const TMAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 128;
+
+/// Converts an internationalized domain to its ASCII form (A-labels) for the wire.
+/// The domain is split into its labels first, so an escaped dot stays inside its label.
+/// The ideographic full stops U+3002, U+FF0E and U+FF61 separate labels like a dot.
+/// Labels that only contain ASCII or aren't UTF-8 (e.g. \255) are kept as they are.
+/// Every other label is processed with UTS #46: it is mapped (e.g. fullwidth letters to ASCII, upper to lower case),
+/// normalized to NFC, checked against the IDNA 2008 rules (e.g. a joiner needs its context) and encoded with punycode.
+///
+/// Example: bücher.de -> xn--bcher-kva.de
+///
+/// # Arguments
+///
+/// * `domain` - The domain in presentation format
+pub fn to_ascii(domain: &str) -> Result<DomainName, CustomError> {
+    let mapped = domain
+        .chars()
+        .map(|c| match c {
+            '\u{3002}' | '\u{ff0e}' | '\u{ff61}' => '.',
+            c => c,
+        })
+        .collect::<String>();
+    let labels = split_labels(&mapped)?
+        .into_iter()
+        .map(|label| to_ascii_label(label, domain))
+        .collect::<Result<Vec<_>, _>>()?;
+    DomainName::from_labels(labels).map_err(|e| match e {
+        CustomError::InvalidName(_, reason) => {
+            CustomError::InvalidName(String::from(domain), reason)
+        }
+        e => e,
+    })
+}
+
+/// Converts one label to an A-label with UTS #46 processing.
+/// A dot inside the label (from an escape) separates parts that are converted on their own.
+///
+/// # Arguments
+///
+/// * `label` - The unescaped label
+/// * `domain` - The domain for the error
+fn to_ascii_label(label: Vec<u8>, domain: &str) -> Result<Vec<u8>, CustomError> {
+    if label.is_ascii() || str::from_utf8(&label).is_err() {
+        return Ok(label);
+    }
+    Uts46::new()
+        .to_ascii(
+            &label,
+            AsciiDenyList::EMPTY,
+            Hyphens::CheckFirstLast,
+            DnsLength::Ignore,
+        )
+        .map(|ascii| ascii.into_owned().into_bytes())
+        .map_err(|_| CustomError::InvalidName(String::from(domain), NameReason::InvalidIdna))
+}
+
+/// Converts the A-labels of a domain back to Unicode.
+/// Labels that aren't valid punycode or would contain control characters are kept as they are.
+///
+/// Example: xn--bcher-kva.de -> bücher.de
+///
+/// # Arguments
+///
+/// * `domain` - The domain in presentation format
+pub fn to_unicode(domain: &str) -> String {
+    domain
+        .split('.')
+        .map(|label| match label.get(..ACE_PREFIX.len()) {
+            Some(prefix) if prefix.eq_ignore_ascii_case(ACE_PREFIX) => {
+                decode(&label[ACE_PREFIX.len()..])
+                    .ok()
+                    .filter(|unicode| !unicode.chars().any(char::is_control))
+                    .unwrap_or_else(|| String::from(label))
+            }
+            _ => String::from(label),
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Encodes a label with punycode (RFC 3492), without the prefix "xn--".
+/// The ASCII characters are copied, followed by a hyphen and the encoded positions of all other characters.
+///
+/// Example: bücher -> bcher-kva
+///
+/// # Arguments
+///
+/// * `input` - The Unicode label
+pub fn encode(input: &str) -> Result<String, CustomError> {
+    let overflow = || CustomError::InvalidName(String::from(input), NameReason::InvalidIdna);
+    let code_points = input.chars().map(u32::from).collect::<Vec<_>>();
+
+    let mut output = input.chars().filter(char::is_ascii).collect::<String>();
+    let basic = output.len() as u32;
+    let mut handled = basic;
+    if basic > 0 {
+        output.push('-');
+    }
+
+    let mut n = INITIAL_N;
+    let mut delta: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+    while (handled as usize) < code_points.len() {
+        let m = code_points
+            .iter()
+            .cloned()
+            .filter(|&c| c >= n)
+            .min()
+            .ok_or_else(overflow)?;
+        delta = (m - n)
+            .checked_mul(handled + 1)
+            .and_then(|d| d.checked_add(delta))
+            .ok_or_else(overflow)?;
+        n = m;
+        for &c in &code_points {
+            if c < n {
+                delta = delta.checked_add(1).ok_or_else(overflow)?;
+            }
+            if c == n {
+                let mut q = delta;
+                let mut k = BASE;
+                loop {
+                    let t = threshold(k, bias);
+                    if q < t {
+                        break;
+                    }
+                    output.push(digit(t + (q - t) % (BASE - t)));
+                    q = (q - t) / (BASE - t);
+                    k += BASE;
+                }
+                output.push(digit(q));
+                bias = adapt(delta, handled + 1, handled == basic);
+                delta = 0;
+                handled += 1;
+            }
+        }
+        delta += 1;
+        n += 1;
+    }
+    Ok(output)
+}
+
+/// Decodes a label that is encoded with punycode (RFC 3492), without the prefix "xn--".
+///
+/// Example: bcher-kva -> bücher
+///
+/// # Arguments
+///
+/// * `input` - The encoded label
+pub fn decode(input: &str) -> Result<String, CustomError> {
+    let invalid = || CustomError::InvalidName(String::from(input), NameReason::InvalidIdna);
+    if !input.is_ascii() {
+        return Err(invalid());
+    }
+
+    let (basic, encoded) = match input.rfind('-') {
+        Some(pos) => (&input[..pos], &input[pos + 1..]),
+        None => ("", input),
+    };
+    let mut output = basic.chars().collect::<Vec<_>>();
+
+    let mut n = INITIAL_N;
+    let mut i: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+    let mut digits = encoded.bytes();
+    while digits.len() > 0 {
+        let old_i = i;
+        let mut w: u32 = 1;
+        let mut k = BASE;
+        loop {
+            let d = digits.next().and_then(digit_value).ok_or_else(invalid)?;
+            i = d
+                .checked_mul(w)
+                .and_then(|x| x.checked_add(i))
+                .ok_or_else(invalid)?;
+            let t = threshold(k, bias);
+            if d < t {
+                break;
+            }
+            w = w.checked_mul(BASE - t).ok_or_else(invalid)?;
+            k += BASE;
+        }
+        let length = output.len() as u32 + 1;
+        bias = adapt(i - old_i, length, old_i == 0);
+        n = n.checked_add(i / length).ok_or_else(invalid)?;
+        i %= length;
+        output.insert(i as usize, std::char::from_u32(n).ok_or_else(invalid)?);
+        i += 1;
+    }
+    Ok(output.into_iter().collect())
+}
+
+/// Returns the threshold of the digit at position k
+fn threshold(k: u32, bias: u32) -> u32 {
+    if k <= bias {
+        TMIN
+    } else if k >= bias + TMAX {
+        TMAX
+    } else {
+        k - bias
+    }
+}
+
+/// Adapts the bias after a code point was encoded or decoded (RFC 3492 6.1)
+///
+/// # Arguments
+///
+/// * `delta` - The delta of the code point
+/// * `points` - The number of code points handled so far
+/// * `first` - True for the first code point
+fn adapt(delta: u32, points: u32, first: bool) -> u32 {
+    let mut delta = if first { delta / DAMP } else { delta / 2 };
+    delta += delta / points;
+    let mut k = 0;
+    while delta > ((BASE - TMIN) * TMAX) / 2 {
+        delta /= BASE - TMIN;
+        k += BASE;
+    }
+    k + (BASE - TMIN + 1) * delta / (delta + SKEW)
+}
+
+/// Returns the character of a digit, 0 to 25 are a to z and 26 to 35 are 0 to 9
+fn digit(d: u32) -> char {
+    if d < 26 {
+        (b'a' + d as u8) as char
+    } else {
+        (b'0' + (d - 26) as u8) as char
+    }
+}
+
+/// Returns the value of a digit, upper and lower case letters are the same
+fn digit_value(c: u8) -> Option<u32> {
+    match c {
+        b'a'..=b'z' => Some(u32::from(c - b'a')),
+        b'A'..=b'Z' => Some(u32::from(c - b'A')),
+        b'0'..=b'9' => Some(u32::from(c - b'0') + 26),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("bücher").unwrap(), "bcher-kva");
+        assert_eq!(encode("münchen").unwrap(), "mnchen-3ya");
+        // RFC 3492 7.1 (B) Chinese (simplified)
+        assert_eq!(
+            encode("他们为什么不说中文").unwrap(),
+            "ihqwcrb4cv8a8dqg056pqjye"
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("bcher-kva").unwrap(), "bücher");
+        assert_eq!(
+            decode("ihqwcrb4cv8a8dqg056pqjye").unwrap(),
+            "他们为什么不说中文"
+        );
+        assert!(decode("bcher-kv!").is_err());
+        assert!(decode("99999999999").is_err());
+    }
+
+    #[test]
+    fn test_to_ascii() {
+        let ascii = |domain: &str| to_ascii(domain).map(|name| name.to_string());
+        assert_eq!(ascii("Bücher.de").unwrap(), "xn--bcher-kva.de");
+        assert_eq!(ascii("例え。テスト").unwrap(), "xn--r8jz45g.xn--zckzah");
+        assert_eq!(ascii("Example.COM").unwrap(), "Example.COM");
+        assert!(ascii("-bücher.de").is_err());
+        // u followed by a combining diaeresis is normalized to ü
+        assert_eq!(ascii("bu\u{308}cher.de").unwrap(), "xn--bcher-kva.de");
+        // fullwidth letters are mapped to ASCII
+        assert_eq!(ascii("ｂüｃher.de").unwrap(), "xn--bcher-kva.de");
+        // a zero width joiner isn't allowed without a virama in front of it (CONTEXTJ)
+        assert!(matches!(
+            to_ascii("a\u{200d}b.de"),
+            Err(CustomError::InvalidName(_, NameReason::InvalidIdna))
+        ));
+        // the escaped dot stays inside the label
+        let name = to_ascii("ü\\.x.de").unwrap();
+        assert_eq!(name.labels().len(), 2);
+        assert_eq!(name.to_string(), "xn--tda\\.x.de");
+        assert_eq!(to_ascii("a\\255.de").unwrap().labels()[0], b"a\xff");
+    }
+
+    #[test]
+    fn test_to_unicode() {
+        assert_eq!(to_unicode("xn--bcher-kva.de"), "bücher.de");
+        assert_eq!(to_unicode("XN--bcher-kva.example"), "bücher.example");
+        assert_eq!(to_unicode("xn--!.de"), "xn--!.de");
+        assert_eq!(to_unicode("example.com"), "example.com");
+    }
+}
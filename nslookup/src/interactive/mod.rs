@@ -0,0 +1,612 @@
+use crate::{build_questions, check_ip, print_response, send_and_parse};
+use nslookup::class::Class;
+use nslookup::client;
+use nslookup::config::{ResolverConfig, SearchList};
+use nslookup::cookie::CookieJar;
+use nslookup::customerror::CustomError;
+use nslookup::edns::{ClientSubnet, Edns, DEFAULT_PAYLOAD, OPTION_CLIENT_SUBNET};
+use nslookup::header::Header;
+use nslookup::qtype::Qtype;
+use nslookup::question::{DnsMessageBuilder, Question};
+use nslookup::rcode::Rcode;
+use nslookup::response::{format_record, Message, Response};
+use std::io::{self, BufRead, Write};
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// Settings that are kept between the queries of an interactive session
+#[derive(Debug, PartialEq)]
+pub struct Session {
+    pub config: ResolverConfig,
+    pub debug: bool,
+    pub qtypes: Vec<Qtype>,
+    pub class: Class,
+    /// Print TTLs with units (e.g. 1h2m) instead of seconds
+    pub ttl_human: bool,
+    /// Ask the server to answer recursively
+    pub recurse: bool,
+    /// The OPT record that is sent with every query, None to send no EDNS
+    pub edns: Option<Edns>,
+    /// The DNS Cookies of the name servers, None to send no cookies
+    pub cookies: Option<CookieJar>,
+}
+
+impl Session {
+    /// Returns a new Session with the default settings
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The name servers that are queried
+    pub fn new(config: ResolverConfig) -> Self {
+        Session {
+            config,
+            debug: false,
+            qtypes: vec![Qtype::A, Qtype::AAAA],
+            class: Class::IN,
+            ttl_human: false,
+            recurse: true,
+            edns: None,
+            cookies: None,
+        }
+    }
+}
+
+/// A single line typed into the interactive shell
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Look up a host name or ip
+    Lookup(String),
+    /// Change the name server
+    Server(IpAddr),
+    /// Change the query type
+    SetType(Qtype),
+    /// Change the query class
+    SetClass(Class),
+    /// Change the port of the name server
+    SetPort(u16),
+    /// Change the timeout in seconds
+    SetTimeout(u64),
+    /// Change how often every name server is asked
+    SetRetry(usize),
+    /// Turn debugging output on or off
+    SetDebug(bool),
+    /// Turn TTLs with units on or off
+    SetTtlHuman(bool),
+    /// Turn recursion desired on or off
+    SetRecurse(bool),
+    /// Turn TCP (virtual circuit) on or off
+    SetVc(bool),
+    /// Turn EDNS on or off
+    SetEdns(bool),
+    /// Change the advertised UDP payload size, turns EDNS on
+    SetBufsize(u16),
+    /// Turn the DNSSEC OK flag on or off, turning it on turns EDNS on
+    SetDnssec(bool),
+    /// Send the network of the client (EDNS Client Subnet) or stop sending it, a subnet turns EDNS on
+    SetSubnet(Option<ClientSubnet>),
+    /// Turn DNS Cookies on or off, cookies need EDNS
+    SetCookie(bool),
+    /// Print the current settings
+    SetAll,
+    /// List a domain
+    Ls(String),
+    /// Print the help message
+    Help,
+    /// Leave the shell
+    Exit,
+    /// An empty line
+    Empty,
+}
+
+/// Parses one line of input into a Command.
+/// Returns an error message if the line can't be understood.
+///
+/// # Arguments
+///
+/// * `line` - The input line
+pub fn parse_command(line: &str) -> Result<Command, String> {
+    let words = line.split_whitespace().collect::<Vec<&str>>();
+    match words.as_slice() {
+        [] => Ok(Command::Empty),
+        ["exit"] => Ok(Command::Exit),
+        ["help"] | ["?"] => Ok(Command::Help),
+        ["server", server] | ["lserver", server] => server
+            .parse::<IpAddr>()
+            .map(Command::Server)
+            .map_err(|_| format!("*** Invalid server adress: {}", server)),
+        ["ls", domain] => Ok(Command::Ls(String::from(*domain))),
+        ["set", option] => parse_set(option),
+        [host] => Ok(Command::Lookup(String::from(*host))),
+        _ => Err(format!("*** Invalid command: {}", line.trim())),
+    }
+}
+
+/// Parses the option of a set command.
+///
+/// # Arguments
+///
+/// * `option` - The part after "set", e.g. "type=MX"
+fn parse_set(option: &str) -> Result<Command, String> {
+    let (key, value) = match option.find('=') {
+        Some(pos) => (&option[..pos], Some(&option[pos + 1..])),
+        None => (option, None),
+    };
+
+    match (key.to_lowercase().as_str(), value) {
+        ("all", None) => Ok(Command::SetAll),
+        ("debug", None) | ("d2", None) => Ok(Command::SetDebug(true)),
+        ("nodebug", None) | ("nod2", None) => Ok(Command::SetDebug(false)),
+        ("ttl-human", None) => Ok(Command::SetTtlHuman(true)),
+        ("nottl-human", None) => Ok(Command::SetTtlHuman(false)),
+        ("recurse", None) | ("rec", None) => Ok(Command::SetRecurse(true)),
+        ("norecurse", None) | ("norec", None) => Ok(Command::SetRecurse(false)),
+        ("vc", None) | ("tcp", None) => Ok(Command::SetVc(true)),
+        ("novc", None) | ("notcp", None) => Ok(Command::SetVc(false)),
+        ("edns", None) => Ok(Command::SetEdns(true)),
+        ("noedns", None) => Ok(Command::SetEdns(false)),
+        ("dnssec", None) => Ok(Command::SetDnssec(true)),
+        ("nodnssec", None) => Ok(Command::SetDnssec(false)),
+        ("subnet", Some(v)) | ("ecs", Some(v)) => v
+            .parse::<ClientSubnet>()
+            .map(|subnet| Command::SetSubnet(Some(subnet)))
+            .map_err(|_| format!("*** Invalid subnet: {}", v)),
+        ("nosubnet", None) | ("noecs", None) => Ok(Command::SetSubnet(None)),
+        ("cookie", None) => Ok(Command::SetCookie(true)),
+        ("nocookie", None) => Ok(Command::SetCookie(false)),
+        ("bufsize", Some(v)) => v
+            .parse::<u16>()
+            .map(Command::SetBufsize)
+            .map_err(|_| format!("*** Invalid bufsize: {}", v)),
+        ("type", Some(v)) | ("querytype", Some(v)) | ("q", Some(v)) | ("ty", Some(v)) => v
+            .parse::<Qtype>()
+            .map(Command::SetType)
+            .map_err(|e| format!("*** {}", e)),
+        ("class", Some(v)) | ("cl", Some(v)) => v
+            .parse::<Class>()
+            .map(Command::SetClass)
+            .map_err(|e| format!("*** {}", e)),
+        ("port", Some(v)) | ("po", Some(v)) => v
+            .parse::<u16>()
+            .map(Command::SetPort)
+            .map_err(|_| format!("*** Invalid port: {}", v)),
+        ("timeout", Some(v)) | ("ti", Some(v)) => v
+            .parse::<u64>()
+            .map(Command::SetTimeout)
+            .map_err(|_| format!("*** Invalid timeout: {}", v)),
+        ("retry", Some(v)) | ("ret", Some(v)) => v
+            .parse::<usize>()
+            .map(Command::SetRetry)
+            .map_err(|_| format!("*** Invalid retry: {}", v)),
+        _ => Err(format!("*** Invalid option: {}", option)),
+    }
+}
+
+/// Runs the interactive shell until "exit" or end of input.
+///
+/// # Arguments
+///
+/// * `session` - The initial settings
+pub fn run(mut session: Session) {
+    let stdin = io::stdin();
+    let mut line = String::new();
+    loop {
+        print!("> ");
+        if io::stdout().flush().is_err() {
+            return;
+        }
+
+        line.clear();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {}
+        }
+
+        match parse_command(&line) {
+            Ok(Command::Exit) => return,
+            Ok(command) => execute(&mut session, command),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+/// Executes a single command and updates the session.
+///
+/// # Arguments
+///
+/// * `session` - The current settings
+/// * `command` - The command to execute
+pub fn execute(session: &mut Session, command: Command) {
+    match command {
+        Command::Lookup(host) => {
+            if let Err(e) = lookup(session, &host) {
+                println!("{}", error_message(&host, &e));
+            }
+        }
+        Command::Server(server) => {
+            session.config.nameservers = vec![server];
+            println!("Default server: {}", server);
+        }
+        Command::SetType(qtype) => session.qtypes = vec![qtype],
+        Command::SetClass(class) => session.class = class,
+        Command::SetPort(port) => session.config.port = port,
+        Command::SetTimeout(secs) => session.config.timeout = Some(Duration::from_secs(secs)),
+        Command::SetRetry(attempts) => session.config.attempts = attempts,
+        Command::SetDebug(debug) => session.debug = debug,
+        Command::SetTtlHuman(ttl_human) => session.ttl_human = ttl_human,
+        Command::SetRecurse(recurse) => session.recurse = recurse,
+        Command::SetVc(tcp) => session.config.tcp = tcp,
+        Command::SetEdns(true) => {
+            session
+                .edns
+                .get_or_insert_with(|| Edns::new(DEFAULT_PAYLOAD));
+        }
+        Command::SetEdns(false) => session.edns = None,
+        Command::SetBufsize(payload) => {
+            session
+                .edns
+                .get_or_insert_with(|| Edns::new(DEFAULT_PAYLOAD))
+                .payload = payload
+        }
+        Command::SetDnssec(true) => {
+            session
+                .edns
+                .get_or_insert_with(|| Edns::new(DEFAULT_PAYLOAD))
+                .dnssec_ok = true
+        }
+        Command::SetSubnet(Some(subnet)) => {
+            let edns = session
+                .edns
+                .take()
+                .unwrap_or_else(|| Edns::new(DEFAULT_PAYLOAD));
+            session.edns = Some(edns.with_client_subnet(&subnet));
+        }
+        Command::SetSubnet(None) => {
+            if let Some(edns) = session.edns.as_mut() {
+                edns.options.retain(|o| o.code != OPTION_CLIENT_SUBNET);
+            }
+        }
+        Command::SetCookie(true) => {
+            session.cookies.get_or_insert_with(CookieJar::new);
+        }
+        Command::SetCookie(false) => session.cookies = None,
+        Command::SetDnssec(false) => {
+            if let Some(edns) = session.edns.as_mut() {
+                edns.dnssec_ok = false;
+            }
+        }
+        Command::SetAll => print_settings(session),
+        Command::Ls(domain) => {
+            if let Err(e) = list(session, &domain) {
+                println!("*** Can't list domain {}: {}", domain, e);
+            }
+        }
+        Command::Help => print_help(),
+        Command::Exit | Command::Empty => {}
+    }
+}
+
+/// Looks up a host with the settings of the session and prints the result.
+/// Host names are combined with the search list of the config, the names are tried
+/// until one of them has records of the type.
+/// Returns an error if the lookup failed, NoData if a name exists but has no records of the type.
+///
+/// # Arguments
+///
+/// * `session` - The current settings
+/// * `host` - The host name or ip
+pub fn lookup(session: &mut Session, host: &str) -> Result<(), CustomError> {
+    let is_nodata: fn(&(Response, _)) -> bool = |(response, _)| response.is_nodata();
+    let mut search = if check_ip(host) {
+        SearchList::new(vec![String::from(host)], is_nodata)
+    } else {
+        session.config.search_list(host, is_nodata)
+    };
+
+    while let Some(name) = search.next_name() {
+        let header = Header::new(client::random_id(), false, 0).with_rd(session.recurse);
+        let messages = build_questions(&name, &session.qtypes, session.class)?;
+        let mut builder = DnsMessageBuilder::new(header, messages);
+        if let Some(edns) = &session.edns {
+            builder = builder.with_edns(edns.clone());
+        }
+        let pack = builder.build_messages()?;
+        let result = send_and_parse(
+            pack,
+            &session.config,
+            session.debug,
+            session.cookies.as_mut(),
+        );
+        if let Some(result) = search.record(result) {
+            let (response, subnet) = result?;
+            print_response(&response, subnet, session.ttl_human);
+            return Ok(());
+        }
+    }
+    // the SOA of the authority section is printed with the error
+    let (response, subnet) = search.finish()?;
+    print_response(&response, subnet, session.ttl_human);
+    Err(CustomError::NoData)
+}
+
+/// Lists all records of a domain with a zone transfer (AXFR) over TCP and prints them.
+/// The SOA record that ends the transfer is printed once.
+/// Returns an error if the server refused the transfer.
+///
+/// # Arguments
+///
+/// * `session` - The current settings
+/// * `domain` - The domain to list
+fn list(session: &Session, domain: &str) -> Result<(), CustomError> {
+    let header = Header::new(client::random_id(), false, 0).with_rd(false);
+    let question = Question::new(domain, Qtype::AXFR)?.with_class(session.class);
+    let messages = DnsMessageBuilder::new(header, vec![question]).build_messages()?;
+    let mut records = vec![];
+    for reply in client::transfer(&messages[0], &session.config)? {
+        let message = Message::parse(&reply)?;
+        match message.rcode() {
+            Rcode::NoError => records.extend(message.answers),
+            rcode => return Err(CustomError::Rcode(rcode)),
+        }
+    }
+    records.pop();
+
+    println!("[{}]", domain);
+    for record in &records {
+        print!(
+            " {}\t{}",
+            record.name,
+            format_record(record, session.ttl_human)
+        );
+    }
+    Ok(())
+}
+
+/// Returns the message that is printed when a lookup failed.
+/// Errors of the server are printed like nslookup does, e.g. "** server can't find x: NXDOMAIN".
+///
+/// # Arguments
+///
+/// * `host` - The host name or ip that was looked up
+/// * `error` - The error of the lookup
+pub fn error_message(host: &str, error: &CustomError) -> String {
+    match error {
+        CustomError::Rcode(rcode) => format!("** server can't find {}: {}", host, rcode),
+        CustomError::EmptyResponse | CustomError::NoData => {
+            format!("*** Can't find {}: No answer", host)
+        }
+        e => e.to_string(),
+    }
+}
+
+/// Prints the current settings
+fn print_settings(session: &Session) {
+    for server in session.config.server_addrs() {
+        println!("Default server: {}", server);
+    }
+    println!();
+    println!("Set options:");
+    println!("  {}debug", if session.debug { "" } else { "no" });
+    println!(
+        "  querytype={}",
+        session
+            .qtypes
+            .iter()
+            .map(|q| q.mnemonic())
+            .collect::<Vec<_>>()
+            .join("+")
+    );
+    println!("  class={}", session.class);
+    println!("  {}ttl-human", if session.ttl_human { "" } else { "no" });
+    println!("  {}recurse", if session.recurse { "" } else { "no" });
+    println!("  {}vc", if session.config.tcp { "" } else { "no" });
+    match &session.edns {
+        Some(edns) => {
+            println!("  edns");
+            println!("  bufsize={}", edns.payload);
+            println!("  {}dnssec", if edns.dnssec_ok { "" } else { "no" });
+            match edns.client_subnet() {
+                Some(subnet) => println!("  subnet={}/{}", subnet.address, subnet.source_prefix),
+                None => println!("  nosubnet"),
+            }
+        }
+        None => println!("  noedns"),
+    }
+    println!(
+        "  {}cookie",
+        if session.cookies.is_some() { "" } else { "no" }
+    );
+    println!("  port={}", session.config.port);
+    match session.config.timeout {
+        Some(t) => println!("  timeout={}", t.as_secs()),
+        None => println!("  timeout=none"),
+    }
+    println!("  retry={}", session.config.attempts);
+    println!("  ndots={}", session.config.ndots);
+    println!("  {}rotate", if session.config.rotate { "" } else { "no" });
+    println!("  srchlist={}", session.config.search.join("/"));
+}
+
+/// Prints the commands of the interactive shell
+fn print_help() {
+    println!("Commands:");
+    println!("NAME            - print info about the host/domain NAME");
+    println!("server IP       - set default server to IP");
+    println!("set all         - print options and current server");
+    println!("set type=X      - set query type (e.g. A, MX, SRV or TYPE123)");
+    println!("set class=X     - set query class (IN, CH, HS or ANY)");
+    println!("set port=X      - set port number to send query on");
+    println!("set timeout=X   - set initial time-out interval in seconds");
+    println!("set retry=X     - set number of retries");
+    println!("set [no]debug   - print debugging information");
+    println!("set [no]recurse - ask for recursive answers");
+    println!("set [no]vc      - always use TCP instead of UDP");
+    println!("set [no]edns    - send an EDNS(0) OPT record");
+    println!("set bufsize=X   - set the advertised UDP payload size (turns on edns)");
+    println!("set [no]dnssec  - ask for DNSSEC records (turns on edns)");
+    println!("set subnet=X    - send the client network X, e.g. 203.0.113.0/24 (turns on edns)");
+    println!("set nosubnet    - stop sending the client network");
+    println!("set [no]cookie  - send DNS Cookies and keep the cookies of the servers");
+    println!("set [no]ttl-human - print TTLs like 1h2m instead of seconds");
+    println!("ls DOMAIN       - list all records of DOMAIN (zone transfer over TCP)");
+    println!("exit            - exit the program");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command_lookup() {
+        assert_eq!(
+            parse_command("google.com\n").unwrap(),
+            Command::Lookup(String::from("google.com"))
+        );
+        assert_eq!(parse_command("  \n").unwrap(), Command::Empty);
+        assert_eq!(parse_command("exit").unwrap(), Command::Exit);
+    }
+
+    #[test]
+    fn test_error_message() {
+        assert_eq!(
+            error_message("foo.example", &CustomError::Rcode(Rcode::NXDomain)),
+            "** server can't find foo.example: NXDOMAIN"
+        );
+        assert_eq!(
+            error_message("foo.example", &CustomError::EmptyResponse),
+            "*** Can't find foo.example: No answer"
+        );
+        assert_eq!(
+            error_message("foo.example", &CustomError::NoData),
+            "*** Can't find foo.example: No answer"
+        );
+    }
+
+    #[test]
+    fn test_lookup_nodata() {
+        // host.corp.example has no records of the type, host doesn't exist
+        let server = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = std::thread::spawn(move || {
+            for _ in 0..4 {
+                let mut buf = [0u8; 512];
+                let (amt, src) = server.recv_from(&mut buf).unwrap();
+                let mut reply = buf[..amt].to_vec();
+                reply[2] |= 0x80;
+                if &reply[13..17] == b"host" && reply[17] == 4 {
+                    reply[9] = 1;
+                    reply.extend_from_slice(&[0, 0, 6, 0, 1, 0, 0, 0, 60, 0, 22, 0, 0]);
+                    reply.extend_from_slice(&[0; 20]);
+                } else {
+                    reply[3] |= 3;
+                }
+                server.send_to(&reply, src).unwrap();
+            }
+        });
+
+        let mut config = ResolverConfig::new(addr.ip());
+        config.port = addr.port();
+        config.search = vec![String::from("corp.example")];
+        let mut session = Session::new(config);
+        assert!(matches!(
+            lookup(&mut session, "host"),
+            Err(CustomError::NoData)
+        ));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_execute_edns() {
+        let mut session = Session::new(ResolverConfig::new("127.0.0.1".parse().unwrap()));
+        execute(&mut session, Command::SetDnssec(false));
+        assert_eq!(session.edns, None);
+        execute(&mut session, Command::SetBufsize(4096));
+        execute(&mut session, Command::SetDnssec(true));
+        assert_eq!(session.edns, Some(Edns::new(4096).with_do(true)));
+        execute(&mut session, Command::SetEdns(true));
+        assert_eq!(session.edns, Some(Edns::new(4096).with_do(true)));
+        execute(&mut session, Command::SetEdns(false));
+        assert_eq!(session.edns, None);
+
+        let subnet = "203.0.113.0/24".parse::<ClientSubnet>().unwrap();
+        execute(&mut session, Command::SetSubnet(Some(subnet)));
+        assert_eq!(session.edns.as_ref().unwrap().client_subnet(), Some(subnet));
+        execute(&mut session, Command::SetSubnet(None));
+        assert_eq!(session.edns, Some(Edns::new(DEFAULT_PAYLOAD)));
+
+        execute(&mut session, Command::SetCookie(true));
+        assert_eq!(session.cookies, Some(CookieJar::new()));
+        execute(&mut session, Command::SetCookie(false));
+        assert_eq!(session.cookies, None);
+    }
+
+    #[test]
+    fn test_parse_command_server() {
+        assert_eq!(
+            parse_command("server 1.1.1.1").unwrap(),
+            Command::Server("1.1.1.1".parse().unwrap())
+        );
+        assert!(parse_command("server foo").is_err());
+    }
+
+    #[test]
+    fn test_parse_command_set() {
+        assert_eq!(
+            parse_command("set type=aaaa").unwrap(),
+            Command::SetType(Qtype::AAAA)
+        );
+        assert_eq!(
+            parse_command("set port=5353").unwrap(),
+            Command::SetPort(5353)
+        );
+        assert_eq!(
+            parse_command("set timeout=3").unwrap(),
+            Command::SetTimeout(3)
+        );
+        assert_eq!(parse_command("set retry=4").unwrap(), Command::SetRetry(4));
+        assert!(parse_command("set retry=-1").is_err());
+        assert_eq!(parse_command("set debug").unwrap(), Command::SetDebug(true));
+        assert_eq!(
+            parse_command("set nodebug").unwrap(),
+            Command::SetDebug(false)
+        );
+        assert_eq!(
+            parse_command("set type=TYPE123").unwrap(),
+            Command::SetType(Qtype::Unknown(123))
+        );
+        assert_eq!(
+            parse_command("set class=ch").unwrap(),
+            Command::SetClass(Class::CH)
+        );
+        assert_eq!(
+            parse_command("set ttl-human").unwrap(),
+            Command::SetTtlHuman(true)
+        );
+        assert_eq!(
+            parse_command("set norecurse").unwrap(),
+            Command::SetRecurse(false)
+        );
+        assert_eq!(parse_command("set vc").unwrap(), Command::SetVc(true));
+        assert_eq!(parse_command("set notcp").unwrap(), Command::SetVc(false));
+        assert_eq!(
+            parse_command("set bufsize=4096").unwrap(),
+            Command::SetBufsize(4096)
+        );
+        assert_eq!(
+            parse_command("set dnssec").unwrap(),
+            Command::SetDnssec(true)
+        );
+        assert_eq!(
+            parse_command("set subnet=2001:db8::/56").unwrap(),
+            Command::SetSubnet(Some("2001:db8::/56".parse().unwrap()))
+        );
+        assert!(parse_command("set subnet=203.0.113.0/33").is_err());
+        assert_eq!(
+            parse_command("set cookie").unwrap(),
+            Command::SetCookie(true)
+        );
+        assert!(parse_command("set bufsize=70000").is_err());
+        assert!(parse_command("set class=FOO").is_err());
+        assert!(parse_command("set type=FOO").is_err());
+        assert!(parse_command("set port=").is_err());
+    }
+}
@@ -1,4 +1,17 @@
+#[cfg(feature = "async")]
+pub mod asyncclient;
+pub mod class;
+pub mod client;
+pub mod config;
+pub mod cookie;
 pub mod customerror;
+pub mod domainname;
+pub mod edns;
+pub mod header;
+pub mod idna;
 pub mod qtype;
 pub mod question;
+pub mod rcode;
+pub mod record;
 pub mod response;
+pub mod srv;
@@ -3,79 +3,254 @@ use std::env;
 use std::str;
 use std::vec::Vec;
 
-mod customerror;
-mod qtype;
-mod question;
-mod response;
-use crate::qtype::Qtype;
-use crate::question::{DnsMessageBuilder, Header, Question};
-use crate::response::Response;
-use customerror::CustomError;
-use std::net::UdpSocket;
+use nslookup::class::Class;
+use nslookup::client;
+use nslookup::config::ResolverConfig;
+use nslookup::cookie::CookieJar;
+use nslookup::customerror::CustomError;
+use nslookup::edns::ClientSubnet;
+use nslookup::header::Header;
+use nslookup::qtype::Qtype;
+use nslookup::question::Question;
+use nslookup::rcode::Rcode;
+use nslookup::response::{Message, Response};
+use std::net::IpAddr;
 use std::process::exit;
 
+mod interactive;
+use interactive::{Command, Session};
+
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() == 2 && &args[1] != "-help" {
-        if check_ip(&args[1]) {
-            println!("Reverse lookup not supported");
-        } else {
-            let header = Header::new(2, false, false);
-            let mut messages = vec![];
-            messages.push(Question::new(&args[1], Qtype::A));
-            messages.push(Question::new(&args[1], Qtype::AAAA));
-
-            let pack = match DnsMessageBuilder::new(header, messages).build_messages() {
-                Ok(val) => val,
-                Err(e) => {
-                    println!("{}", e);
-                    exit(1)
-                }
-            };
-
-            match send_and_parse(pack) {
-                Ok(_) => exit(0),
-                Err(e) => {
-                    println!("{}", e.to_string());
-                    exit(1)
-                }
+    if args.iter().any(|a| a == "-help") {
+        print_usage();
+        return;
+    }
+
+    let (mut session, host) = match parse_args(&args[1..]) {
+        Ok(val) => val,
+        Err(e) => {
+            println!("{}", e);
+            print_usage();
+            exit(1)
+        }
+    };
+
+    match host {
+        None => interactive::run(session),
+        Some(host) => match interactive::lookup(&mut session, &host) {
+            Ok(_) => exit(0),
+            Err(e) => {
+                println!("{}", interactive::error_message(&host, &e));
+                exit(exit_code(&e))
             }
+        },
+    }
+}
+
+/// Returns the exit code for an error, every kind of failure has its own code.
+/// 1 is used for all errors that aren't listed, e.g. invalid arguments.
+/// # Arguments
+/// * `error` - the error of the lookup
+fn exit_code(error: &CustomError) -> i32 {
+    match error {
+        CustomError::Rcode(Rcode::NXDomain) => 2,
+        CustomError::EmptyResponse | CustomError::NoData => 3,
+        CustomError::Rcode(Rcode::ServFail) => 4,
+        CustomError::Rcode(Rcode::Refused) => 5,
+        CustomError::Rcode(_) => 6,
+        CustomError::IoError(_) | CustomError::NoNameserver | CustomError::Timeout(_) => 7,
+        CustomError::Malformed(..) | CustomError::ResponseError | CustomError::Overflow => 8,
+        _ => 1,
+    }
+}
+
+/// Prints the help message
+fn print_usage() {
+    println!("Usage is: nslookup [-option...] [Host Name | -] [Server] | -help");
+    println!("nslookup (Starts the interactive mode)");
+    println!("nslookup - 1.1.1.1 (Starts the interactive mode using the given Server)");
+    println!("nslookup foo.bar.com (Returns IP Address for Host Name)");
+    println!("nslookup foo.bar.com 1.1.1.1 (Asks the given Server instead of the ones in /etc/resolv.conf)");
+    println!("nslookup 8.8.8.8 (Returns Host Name for IP Address)");
+    println!("nslookup -port=5353 foo.bar.com (Asks the Server on the given Port)");
+    println!(
+        "nslookup -class=CH -type=TXT version.bind 1.1.1.1 (Asks for the version of the Server)"
+    );
+    println!("nslookup -help (Returns this Help Message)");
+    println!(
+        "Exit codes: 0 found, 2 NXDOMAIN, 3 no data, 4 SERVFAIL, 5 REFUSED, 6 other server error,"
+    );
+    println!("            7 network error, 8 malformed response, 1 any other error");
+    println!(
+        "Options: -type=X, -class=X, -port=X, -timeout=X, -retry=X, -[no]debug, -[no]recurse,"
+    );
+    println!("         -[no]vc, --[no]tcp, -[no]edns, -bufsize=X, -[no]dnssec,");
+    println!("         --subnet X (e.g. 203.0.113.0/24), --nosubnet, -[no]cookie, --[no]ttl-human");
+}
+
+/// Parses the command line arguments into a Session and the host that is looked up.
+/// Options start with one or two hyphens and are the same as the set commands of the interactive mode.
+/// The host is None if interactive mode is requested.
+/// # Arguments
+/// * `args` - the arguments without the program name
+fn parse_args(args: &[String]) -> Result<(Session, Option<String>), String> {
+    let mut session = Session::new(ResolverConfig::default());
+    let mut positional = vec![];
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg.len() > 1 && arg.starts_with('-') {
+            let mut option = String::from(arg.trim_start_matches('-'));
+            // the subnet may also be given as the next argument, e.g. --subnet 203.0.113.0/24
+            if option == "subnet" {
+                let subnet = args
+                    .next()
+                    .ok_or_else(|| format!("*** Missing subnet: {}", arg))?;
+                option = format!("subnet={}", subnet);
+            }
+            match interactive::parse_command(&format!("set {}", option))? {
+                command @ Command::SetType(_)
+                | command @ Command::SetClass(_)
+                | command @ Command::SetPort(_)
+                | command @ Command::SetTimeout(_)
+                | command @ Command::SetRetry(_)
+                | command @ Command::SetDebug(_)
+                | command @ Command::SetTtlHuman(_)
+                | command @ Command::SetRecurse(_)
+                | command @ Command::SetVc(_)
+                | command @ Command::SetEdns(_)
+                | command @ Command::SetBufsize(_)
+                | command @ Command::SetDnssec(_)
+                | command @ Command::SetSubnet(_)
+                | command @ Command::SetCookie(_) => interactive::execute(&mut session, command),
+                _ => return Err(format!("*** Invalid option: {}", arg)),
+            }
+        } else {
+            positional.push(arg.as_str());
         }
-    } else {
-        println!("Usage is: nslookup [Host Name] | -help");
-        println!("nslookup foo.bar.com (Returns IP Address for Host Name)");
-        println!("nslookup -help (Returns this Help Message)");
     }
+
+    let host = match positional.as_slice() {
+        [] => None,
+        [host] | [host, _] => Some(host.to_string()).filter(|h| h != "-"),
+        _ => return Err(String::from("*** Too many arguments")),
+    };
+    if let Some(server) = positional.get(1) {
+        let ip = server
+            .parse::<IpAddr>()
+            .map_err(|_| format!("*** Invalid server adress: {}", server))?;
+        session.config.nameservers = vec![ip];
+    }
+    Ok((session, host))
 }
 
-/// Returns true if argument is an IP
+/// Returns true if argument is an IPv4 or IPv6 adress
 /// # Arguments
-/// * `bytes` - bytes that we want to parse
+/// * `ip` - the argument that we want to parse
 fn check_ip(ip: &str) -> bool {
-    ip.split('.').map(|s| s.parse::<i32>().is_ok()).count() == 4
+    ip.parse::<IpAddr>().is_ok()
+}
+
+/// Returns the Questions for a host or an error if the host name isn't a valid domain.
+/// An ip results in a single PTR Question, a host name in one Question per Qtype.
+/// # Arguments
+/// * `host` - host name or ip
+/// * `qtypes` - the Qtypes that are asked for a host name
+/// * `class` - the Class of the Questions
+fn build_questions(
+    host: &str,
+    qtypes: &[Qtype],
+    class: Class,
+) -> Result<Vec<Question>, CustomError> {
+    if check_ip(host) {
+        Ok(vec![
+            Question::reverse(&host.parse::<IpAddr>().unwrap()).with_class(class)
+        ])
+    } else {
+        qtypes
+            .iter()
+            .map(|&q| Ok(Question::new(host, q)?.with_class(class)))
+            .collect()
+    }
 }
 
-/// Creates a DNS-Package which contains a Header and a Question.
-/// Sends and retreives it.
-/// Prints Records of the receiving packet.
-/// The response contains the same Header + Question and in addition one or more concatenated Answers.
+/// Sends all DNS-Packages of a name at once and returns one combined Response
+/// together with the client subnet the server answered for, if it used one.
+/// The records are kept in the order of the questions, no matter which reply came first.
+/// Packages go over TCP if the config asks for it or the UDP reply was truncated.
 /// # Arguments
-/// * `message` - u8 vector, containing Header and Question
-pub fn send_and_parse(messages: Vec<Vec<u8>>) -> Result<(()), CustomError> {
-    for message in messages {
-        let sock = UdpSocket::bind("0.0.0.0:0")?;
-        let mut buf = [0u8; 4096];
+/// * `messages` - u8 vectors, each containing Header and Question
+/// * `config` - the name servers to ask
+/// * `debug` - print the raw packets
+/// * `cookies` - the DNS Cookies of the name servers, None to send no cookies
+pub fn send_and_parse(
+    messages: Vec<Vec<u8>>,
+    config: &ResolverConfig,
+    debug: bool,
+    cookies: Option<&mut CookieJar>,
+) -> Result<(Response, Option<ClientSubnet>), CustomError> {
+    let replies = client::query_all(&messages, config, cookies)?;
 
-        sock.send_to(&message[..], "8.8.8.8:53")?;
-        let amt = sock.recv(&mut buf)?;
+    if debug {
+        for (message, buf) in messages.iter().zip(&replies) {
+            println!("------------");
+            println!("Sent {} bytes:", message.len());
+            println!("{}", hex_dump(message));
+            println!("Got answer ({} bytes):", buf.len());
+            println!("{}", hex_dump(buf));
+            if let Ok(header) = Header::parse(buf) {
+                println!("{}", header);
+            }
+            if let Some(edns) = Message::parse(buf).ok().and_then(|m| m.edns) {
+                println!("{}", edns);
+            }
+            println!("------------");
+        }
+    }
+
+    let response = Response::from_replies(&replies)?;
+    let subnet = replies
+        .iter()
+        .find_map(|buf| Message::parse(buf).ok()?.edns?.client_subnet());
+    Ok((response, subnet))
+}
 
-        let response = Response::parse_response(&buf[0..amt], message[..].len())?;
-        println!("{}", response);
+/// Prints a Response and the client subnet it is valid for
+/// # Arguments
+/// * `response` - the combined Response of a name
+/// * `subnet` - the client subnet of the reply, None if the server didn't use one
+/// * `ttl_human` - print TTLs with units instead of seconds
+pub fn print_response(response: &Response, subnet: Option<ClientSubnet>, ttl_human: bool) {
+    println!("{}", response.format(ttl_human));
+    // the scope tells for which network the answer is valid, e.g. for a CDN
+    if let Some(subnet) = subnet {
+        println!(
+            "Client subnet: {}/{}, scope /{}",
+            subnet.address, subnet.source_prefix, subnet.scope_prefix
+        );
     }
-    Ok(())
+}
+
+/// Returns the bytes as hex, 16 bytes per line
+/// # Arguments
+/// * `bytes` - the bytes to format
+fn hex_dump(bytes: &[u8]) -> String {
+    bytes
+        .chunks(16)
+        .map(|line| {
+            line.iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .map(|line| format!("    {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
@@ -91,4 +266,92 @@ mod tests {
     fn test_check_ip_nonip() {
         assert_eq!(check_ip("127.0.0.1.1"), false);
     }
+    #[test]
+    fn test_check_ip_ipv6() {
+        assert!(check_ip("2001:4860:4860::8888"));
+    }
+    #[test]
+    fn test_build_questions() {
+        assert_eq!(
+            build_questions("google.com", &[Qtype::A, Qtype::AAAA], Class::IN)
+                .unwrap()
+                .len(),
+            2
+        );
+        let reverse = build_questions("8.8.8.8", &[Qtype::A, Qtype::AAAA], Class::IN).unwrap();
+        assert_eq!(reverse.len(), 1);
+        assert!(reverse[0].qtype == Qtype::PTR);
+    }
+    #[test]
+    fn test_parse_args() {
+        let args = vec![
+            String::from("-port=5353"),
+            String::from("google.com"),
+            String::from("::1"),
+        ];
+        let (session, host) = parse_args(&args).unwrap();
+        assert_eq!(host, Some(String::from("google.com")));
+        assert_eq!(
+            session.config.server_addrs(),
+            vec!["[::1]:5353".parse().unwrap()]
+        );
+    }
+    #[test]
+    fn test_parse_args_interactive() {
+        let args = vec![String::from("-"), String::from("1.1.1.1")];
+        let (session, host) = parse_args(&args).unwrap();
+        assert_eq!(host, None);
+        assert_eq!(
+            session.config.nameservers,
+            vec!["1.1.1.1".parse::<IpAddr>().unwrap()]
+        );
+        assert!(parse_args(&[String::from("-port=x")]).is_err());
+        assert!(parse_args(&[String::from("--ls")]).is_err());
+        assert!(parse_args(&[String::from("a"), String::from("b")]).is_err());
+    }
+    #[test]
+    fn test_parse_args_class() {
+        let args = vec![
+            String::from("-class=CH"),
+            String::from("-type=TXT"),
+            String::from("--ttl-human"),
+            String::from("--tcp"),
+            String::from("version.bind"),
+        ];
+        let (session, host) = parse_args(&args).unwrap();
+        assert_eq!(host, Some(String::from("version.bind")));
+        assert_eq!(session.class, Class::CH);
+        assert!(session.qtypes == vec![Qtype::TXT]);
+        assert!(session.ttl_human);
+        assert!(session.config.tcp);
+    }
+    #[test]
+    fn test_parse_args_subnet() {
+        let args = vec![
+            String::from("--subnet"),
+            String::from("203.0.113.0/24"),
+            String::from("example.com"),
+        ];
+        let (session, host) = parse_args(&args).unwrap();
+        assert_eq!(host, Some(String::from("example.com")));
+        let subnet = session.edns.unwrap().client_subnet().unwrap();
+        assert_eq!(subnet.to_string(), "203.0.113.0/24/0");
+        assert!(parse_args(&[String::from("-subnet=2001:db8::/56")]).is_ok());
+        assert!(parse_args(&[String::from("--subnet")]).is_err());
+    }
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(exit_code(&CustomError::Rcode(Rcode::NXDomain)), 2);
+        assert_eq!(exit_code(&CustomError::NoData), 3);
+        assert_eq!(exit_code(&CustomError::Rcode(Rcode::ServFail)), 4);
+        assert_eq!(exit_code(&CustomError::Rcode(Rcode::BadCookie)), 6);
+        assert_eq!(exit_code(&CustomError::NoNameserver), 7);
+        let server = "127.0.0.1:53".parse().unwrap();
+        assert_eq!(exit_code(&CustomError::Timeout(server)), 7);
+        assert_eq!(exit_code(&CustomError::UnknownQtype(String::new())), 1);
+    }
+    #[test]
+    fn test_hex_dump() {
+        assert_eq!(hex_dump(&[0, 2, 129]), "    00 02 81");
+    }
 }
@@ -1,23 +1,65 @@
 use crate::customerror::CustomError;
 use std::fmt;
+use std::str::FromStr;
 
 /// Variation of Qtype
-#[derive(PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(PartialEq, Clone, Copy)]
 pub enum Qtype {
     /// IPv4
     A,
     /// IPv6
     AAAA,
+    /// authoritative name server
+    NS,
     /// canonical name record
     CNAME,
+    /// start of a zone of authority
+    SOA,
+    /// domain name pointer (reverse lookup)
+    PTR,
+    /// mail exchange
+    MX,
+    /// text strings
+    TXT,
+    /// service location
+    SRV,
+    /// EDNS pseudo-record, only found in the additional section
+    OPT,
+    /// zone transfer, only asked over TCP
+    AXFR,
+    /// any other type, kept by its number (RFC 3597)
+    Unknown(u16),
 }
 
+/// All Qtypes that can be asked for by their mnemonic
+const QTYPES: [Qtype; 9] = [
+    Qtype::A,
+    Qtype::AAAA,
+    Qtype::NS,
+    Qtype::CNAME,
+    Qtype::SOA,
+    Qtype::PTR,
+    Qtype::MX,
+    Qtype::TXT,
+    Qtype::SRV,
+];
+
 impl fmt::Display for Qtype {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         match *self {
             Qtype::A => write!(f, "IPv4"),
             Qtype::AAAA => write!(f, "IPv6"),
+            Qtype::NS => write!(f, "NS"),
             Qtype::CNAME => write!(f, "CNAME"),
+            Qtype::SOA => write!(f, "SOA"),
+            Qtype::PTR => write!(f, "PTR"),
+            Qtype::MX => write!(f, "MX"),
+            Qtype::TXT => write!(f, "TXT"),
+            Qtype::SRV => write!(f, "SRV"),
+            Qtype::OPT => write!(f, "OPT"),
+            Qtype::AXFR => write!(f, "AXFR"),
+            Qtype::Unknown(x) => write!(f, "TYPE{}", x),
         }
     }
 }
@@ -27,21 +69,59 @@ impl fmt::Debug for Qtype {
         match *self {
             Qtype::A => write!(f, "IPv4"),
             Qtype::AAAA => write!(f, "IPv6"),
+            Qtype::NS => write!(f, "NS"),
             Qtype::CNAME => write!(f, "CNAME"),
+            Qtype::SOA => write!(f, "SOA"),
+            Qtype::PTR => write!(f, "PTR"),
+            Qtype::MX => write!(f, "MX"),
+            Qtype::TXT => write!(f, "TXT"),
+            Qtype::SRV => write!(f, "SRV"),
+            Qtype::OPT => write!(f, "OPT"),
+            Qtype::AXFR => write!(f, "AXFR"),
+            Qtype::Unknown(x) => write!(f, "TYPE{}", x),
         }
     }
 }
 
 impl Qtype {
     /// Returns the the Enum value
-    pub fn value(&self) -> u8 {
+    pub fn value(&self) -> u16 {
         match *self {
-            Qtype::A => 1 as u8,
-            Qtype::AAAA => 28 as u8,
-            Qtype::CNAME => 5 as u8,
+            Qtype::A => 1,
+            Qtype::AAAA => 28,
+            Qtype::NS => 2,
+            Qtype::CNAME => 5,
+            Qtype::SOA => 6,
+            Qtype::PTR => 12,
+            Qtype::MX => 15,
+            Qtype::TXT => 16,
+            Qtype::SRV => 33,
+            Qtype::OPT => 41,
+            Qtype::AXFR => 252,
+            Qtype::Unknown(x) => x,
         }
     }
-    /// Returns Enum variant for given value
+    /// Returns the mnemonic used in zone files and by the interactive shell.
+    /// Unknown types are written as TYPE followed by the number.
+    pub fn mnemonic(&self) -> String {
+        match *self {
+            Qtype::A => String::from("A"),
+            Qtype::AAAA => String::from("AAAA"),
+            Qtype::NS => String::from("NS"),
+            Qtype::CNAME => String::from("CNAME"),
+            Qtype::SOA => String::from("SOA"),
+            Qtype::PTR => String::from("PTR"),
+            Qtype::MX => String::from("MX"),
+            Qtype::TXT => String::from("TXT"),
+            Qtype::SRV => String::from("SRV"),
+            Qtype::OPT => String::from("OPT"),
+            Qtype::AXFR => String::from("AXFR"),
+            Qtype::Unknown(x) => format!("TYPE{}", x),
+        }
+    }
+
+    /// Returns Enum variant for given value.
+    /// Values without an own variant become Unknown, only values that don't fit in 16 bit are an error.
     ///
     /// # Arguments
     /// `value` - actual Qtype value
@@ -49,8 +129,62 @@ impl Qtype {
         match value {
             1 => Ok(Qtype::A),
             28 => Ok(Qtype::AAAA),
+            2 => Ok(Qtype::NS),
             5 => Ok(Qtype::CNAME),
+            6 => Ok(Qtype::SOA),
+            12 => Ok(Qtype::PTR),
+            15 => Ok(Qtype::MX),
+            16 => Ok(Qtype::TXT),
+            33 => Ok(Qtype::SRV),
+            41 => Ok(Qtype::OPT),
+            252 => Ok(Qtype::AXFR),
+            x if x <= usize::from(u16::MAX) => Ok(Qtype::Unknown(x as u16)),
             _ => Err(CustomError::QtypeNotSupported(value)),
         }
     }
 }
+
+impl FromStr for Qtype {
+    type Err = CustomError;
+
+    /// Returns Enum variant for the given mnemonic (e.g. "AAAA"), case insensitive.
+    /// Any type can be given as TYPE followed by its number (e.g. "TYPE65534").
+    ///
+    /// # Arguments
+    /// `s` - the mnemonic
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(q) = QTYPES.iter().find(|q| q.mnemonic().eq_ignore_ascii_case(s)) {
+            return Ok(*q);
+        }
+        match s.get(..4) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("TYPE") => s[4..]
+                .parse::<u16>()
+                .map_err(|_| CustomError::UnknownQtype(String::from(s)))
+                .and_then(|x| Qtype::get_qtype(usize::from(x))),
+            _ => Err(CustomError::UnknownQtype(String::from(s))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        assert!("aaaa".parse::<Qtype>().unwrap() == Qtype::AAAA);
+        assert!("TYPE1".parse::<Qtype>().unwrap() == Qtype::A);
+        assert!("type65534".parse::<Qtype>().unwrap() == Qtype::Unknown(65534));
+        assert!("TYPE65536".parse::<Qtype>().is_err());
+        assert!("FOO".parse::<Qtype>().is_err());
+    }
+
+    #[test]
+    fn test_unknown_mnemonic() {
+        assert_eq!(Qtype::Unknown(123).mnemonic(), "TYPE123");
+        assert_eq!(Qtype::Unknown(123).value(), 123);
+        assert_eq!(Qtype::MX.mnemonic(), "MX");
+        assert!(Qtype::get_qtype(41).unwrap() == Qtype::OPT);
+        assert!(Qtype::get_qtype(252).unwrap() == Qtype::AXFR);
+    }
+}
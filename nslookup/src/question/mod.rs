@@ -1,85 +1,81 @@
+use crate::class::Class;
+use crate::client;
 use crate::customerror::CustomError;
+use crate::domainname::DomainName;
+use crate::edns::Edns;
+use crate::header::Header;
+use crate::idna;
 use crate::qtype::Qtype;
-use std::fmt::Write;
-use std::num::ParseIntError;
+use std::net::IpAddr;
 use std::str;
 use std::vec::Vec;
 
-/// Create DNS-Headers
-pub struct Header {
-    pub id: u16,
-    pub qr: bool,
-    pub opcode: bool,
-}
-
-impl Header {
-    /// Returns a new Header
-    ///
-    /// # Arguments
-    ///
-    /// * `id` - arbitrary 16 bit identifier
-    /// * `qr` - specify if query or response
-    /// * `opcode` - qtype (A or AAAA) query type (standard/inverse)
-    pub fn new(id: u16, qr: bool, opcode: bool) -> Self {
-        Header { id, qr, opcode }
-    }
-
-    /// Parses the Header in an u8 Vector
-    pub fn get_header(&self) -> Result<Vec<u8>, CustomError> {
-        let queryparams = format!("{}000{}00100000000", self.qr as i32, self.opcode as i32);
-        let m = format!(
-            "{:0>4x}{}0001000000000000",
-            self.id,
-            binary_to_hex(&queryparams)?
-        );
-        Ok(decode(&m)?)
-    }
-}
-
 /// Create DNS-Questions
+#[derive(Debug, PartialEq, Clone)]
 pub struct Question {
-    pub url: String,
+    pub name: DomainName,
     pub qtype: Qtype,
+    pub class: Class,
 }
 
 impl Question {
-    /// Returns a new Question
+    /// Returns a new Question or an error if the url isn't a valid domain.
+    /// Internationalized labels are converted to A-labels (e.g. bücher -> xn--bcher-kva) first.
+    /// The url is validated as DomainName in presentation format,
+    /// an invalid url (e.g. an empty label or a label longer than 63 bytes) is an error.
     ///
     /// # Arguments
     ///
-    /// * `header` - u8 Vec of specified header
     /// * `url` - url
     /// * `qtype` - qtype (A or AAAA)
-    pub fn new(url: &str, qtype: Qtype) -> Self {
+    pub fn new(url: &str, qtype: Qtype) -> Result<Self, CustomError> {
+        Ok(Question::from_name(idna::to_ascii(url)?, qtype))
+    }
+
+    /// Returns a new Question for an already validated domain
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The domain
+    /// * `qtype` - The type of the records
+    pub fn from_name(name: DomainName, qtype: Qtype) -> Self {
         Question {
-            url: String::from(url),
+            name,
             qtype,
+            class: Class::IN,
         }
     }
 
+    /// Returns the Question with another class than IN
+    ///
+    /// Example: version.bind CH TXT
+    ///
+    /// # Arguments
+    ///
+    /// * `class` - The class
+    pub fn with_class(mut self, class: Class) -> Self {
+        self.class = class;
+        self
+    }
+
+    /// Returns a new PTR Question for the reverse lookup of an ip
+    ///
+    /// # Arguments
+    ///
+    /// * `ip` - The IPv4 or IPv6 adress
+    pub fn reverse(ip: &IpAddr) -> Self {
+        let name = reverse_name(ip)
+            .parse::<DomainName>()
+            .expect("a reverse name is always valid");
+        Question::from_name(name, Qtype::PTR)
+    }
+
     /// Parses the Question in an u8 Vector.
     pub fn get_question(&self) -> Vec<u8> {
-        let mut vec = vec![];
-        let rest = vec![0, 0, self.qtype.value(), 0, 1];
-        if !self.url.is_empty() {
-            for x in self.url.split('.').collect::<Vec<&str>>() {
-                let url_bytes: Vec<_> = x.bytes().collect();
-                let len = url_bytes
-                    .len()
-                    .to_be_bytes()
-                    .to_vec()
-                    .into_iter()
-                    .filter(|&i| i != 0)
-                    .collect::<Vec<_>>();
-                vec.extend(len);
-                vec.extend(url_bytes);
-            }
-            vec.extend(rest);
-            vec
-        } else {
-            vec.extend(rest);
-            vec
-        }
+        let mut vec = self.name.to_wire();
+        vec.extend_from_slice(&self.qtype.value().to_be_bytes());
+        vec.extend_from_slice(&self.class.value().to_be_bytes());
+        vec
     }
 }
 
@@ -87,6 +83,8 @@ impl Question {
 pub struct DnsMessageBuilder {
     pub header: Header,
     pub questions: Vec<Question>,
+    /// The OPT record that is sent with every question, None to send no EDNS
+    pub edns: Option<Edns>,
 }
 
 impl DnsMessageBuilder {
@@ -97,90 +95,123 @@ impl DnsMessageBuilder {
     /// * `header` - A header struct
     /// * `questions` - A vector of questions
     pub fn new(header: Header, questions: Vec<Question>) -> Self {
-        DnsMessageBuilder { header, questions }
+        DnsMessageBuilder {
+            header,
+            questions,
+            edns: None,
+        }
+    }
+
+    /// Returns the DNSPackage with an OPT record in the additional section of every message
+    ///
+    /// # Arguments
+    ///
+    /// * `edns` - The payload size, flags and options to send
+    pub fn with_edns(mut self, edns: Edns) -> Self {
+        self.edns = Some(edns);
+        self
     }
 
-    /// Builds the dns messages
+    /// Builds the dns messages, one for every question.
+    /// The first message gets the ID of the header, every other one a random ID of its own,
+    /// so the IDs can't be guessed from each other and the replies can be told apart when they are sent at once.
     pub fn build_messages(&self) -> Result<Vec<Vec<u8>>, CustomError> {
+        let mut header = self.header.clone();
+        let mut opt = vec![];
+        if let Some(edns) = &self.edns {
+            header.arcount = 1;
+            opt = edns.to_wire();
+        }
+
         let mut res = vec![];
+        let mut ids = vec![];
         for q in &self.questions {
-            res.push([self.header.get_header()?, q.get_question()].concat());
+            res.push([header.get_header(), q.get_question(), opt.clone()].concat());
+            ids.push(header.id);
+            while ids.contains(&header.id) {
+                header.id = client::random_id();
+            }
         }
         Ok(res)
     }
 }
 
-/// returns binary as hex representation
-/// # Arguments
-/// * `binary` 16 bit binary
-fn binary_to_hex(binary: &str) -> Result<String, CustomError> {
-    let mut s = String::with_capacity(4);
-    for x in binary.chars().collect::<Vec<char>>().chunks_exact(4) {
-        write!(&mut s, "{:x}", recursive_find(0, &x))?;
-    }
-    Ok(s)
-}
-
-/// returns u16 representation of the given chunk so that it can be formated and concatenated to a hex
+/// Returns the in-addr.arpa or ip6.arpa domain of an ip.
+/// IPv4 octets are written in reverse order,
+/// IPv6 adresses are split into nibbles which are written in reverse order.
+///
+/// Example: 8.8.4.4 -> 4.4.8.8.in-addr.arpa
+///
 /// # Arguments
-/// * `number` - number
-/// * `chars` - chunk
-fn recursive_find(number: u16, chars: &[char]) -> u16 {
-    let mut result = number;
-    let mut chars_rec = chars.to_owned();
-    let position = chars_rec.iter().position(|&x| x == '1');
-
-    match position {
-        Some(val) => {
-            chars_rec[val] = '0';
-            result += recursive_find(u16_from_position(val), &chars_rec);
-            result
+/// * `ip` - The IPv4 or IPv6 adress
+pub fn reverse_name(ip: &IpAddr) -> String {
+    match ip {
+        IpAddr::V4(v4) => {
+            let octets = v4
+                .octets()
+                .iter()
+                .rev()
+                .map(|o| o.to_string())
+                .collect::<Vec<_>>();
+            format!("{}.in-addr.arpa", octets.join("."))
+        }
+        IpAddr::V6(v6) => {
+            let nibbles = v6
+                .octets()
+                .iter()
+                .rev()
+                .map(|o| format!("{:x}.{:x}", o & 0x0f, o >> 4))
+                .collect::<Vec<_>>();
+            format!("{}.ip6.arpa", nibbles.join("."))
         }
-        None => result,
-    }
-}
-
-/// returns u16 representation of given position
-/// # Arguments
-/// * `usize` position of 1
-fn u16_from_position(position: usize) -> u16 {
-    match position {
-        0 => 8,
-        1 => 4,
-        2 => 2,
-        3 => 1,
-        _ => 0,
     }
 }
 
-/// Returns a u8 vector on success else an Error
-/// # Arguments
-/// * `hex` - a hex &str that we want to parse
-fn decode(hex: &str) -> Result<Vec<u8>, ParseIntError> {
-    (0..hex.len())
-        .step_by(2)
-        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
-        .collect()
-}
-
 #[cfg(test)]
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
+    use crate::customerror::NameReason;
+
+    #[test]
+    fn test_build_messages_ids() {
+        let questions = vec![
+            Question::new("example.com", Qtype::A).unwrap(),
+            Question::new("example.com", Qtype::AAAA).unwrap(),
+            Question::new("example.com", Qtype::MX).unwrap(),
+        ];
+        let messages = DnsMessageBuilder::new(Header::new(0xffff, false, 0), questions)
+            .build_messages()
+            .unwrap();
+        assert_eq!(messages[0][..2], [0xff, 0xff]);
+        // the other IDs are random, but no two messages share one
+        let mut ids = messages.iter().map(|m| [m[0], m[1]]).collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), 3);
+    }
 
     #[test]
-    fn test_u16_from_position() {
-        assert_eq!(u16_from_position(0), 8);
+    fn test_new_validates_name() {
+        assert!(matches!(
+            Question::new("a..b", Qtype::A),
+            Err(CustomError::InvalidName(_, NameReason::EmptyLabel))
+        ));
+        let question = Question::new("example.com", Qtype::A).unwrap();
+        assert_eq!(question.name, "example.com".parse::<DomainName>().unwrap());
     }
+
     #[test]
-    fn test_decode() {
-        let message = "AAAA01000001000000000000076578616d706c6503636f6d0000010001";
-        let message_decode = b"\xAA\xAA\x01\x00\x00\x01\x00\x00\x00\x00\x00\x00\x07\x65\x78\x61\x6d\x70\x6c\x65\x03\x63\x6f\x6d\x00\x00\x01\x00\x01";
-        assert_eq!(decode(message).unwrap(), message_decode);
+    fn test_reverse_name_v4() {
+        let ip: IpAddr = "8.8.4.4".parse().unwrap();
+        assert_eq!(reverse_name(&ip), "4.4.8.8.in-addr.arpa");
     }
     #[test]
-    fn test_binary_to_hex() {
-        let bin = "0000100100000000";
-        assert_eq!(binary_to_hex(bin).unwrap(), "0900");
+    fn test_reverse_name_v6() {
+        let ip: IpAddr = "2001:db8::567:89ab".parse().unwrap();
+        assert_eq!(
+            reverse_name(&ip),
+            "b.a.9.8.7.6.5.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa"
+        );
     }
 }
@@ -0,0 +1,168 @@
+use std::fmt;
+
+/// Variation of Rcode, the result of a query.
+/// The header only has room for 4 bits, EDNS adds 8 more bits in the OPT record.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Rcode {
+    /// no error
+    NoError,
+    /// the server couldn't interpret the query
+    FormErr,
+    /// the server failed to process the query
+    ServFail,
+    /// the domain doesn't exist
+    NXDomain,
+    /// the kind of query isn't implemented
+    NotImp,
+    /// the server refuses to answer for policy reasons
+    Refused,
+    /// a name exists that shouldn't (dynamic update)
+    YXDomain,
+    /// a RRset exists that shouldn't (dynamic update)
+    YXRRSet,
+    /// a RRset doesn't exist that should (dynamic update)
+    NXRRSet,
+    /// the server isn't authoritative for the zone
+    NotAuth,
+    /// a name isn't in the zone
+    NotZone,
+    /// the DSO type isn't implemented
+    DSOTypeNI,
+    /// the EDNS version isn't supported
+    BadVers,
+    /// the key isn't recognized
+    BadKey,
+    /// the signature is out of the time window
+    BadTime,
+    /// bad TKEY mode
+    BadMode,
+    /// duplicate key name
+    BadName,
+    /// the algorithm isn't supported
+    BadAlg,
+    /// bad truncation
+    BadTrunc,
+    /// the server cookie is bad or missing
+    BadCookie,
+    /// any other value
+    Unknown(u16),
+}
+
+impl fmt::Display for Rcode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            Rcode::NoError => write!(f, "NOERROR"),
+            Rcode::FormErr => write!(f, "FORMERR"),
+            Rcode::ServFail => write!(f, "SERVFAIL"),
+            Rcode::NXDomain => write!(f, "NXDOMAIN"),
+            Rcode::NotImp => write!(f, "NOTIMP"),
+            Rcode::Refused => write!(f, "REFUSED"),
+            Rcode::YXDomain => write!(f, "YXDOMAIN"),
+            Rcode::YXRRSet => write!(f, "YXRRSET"),
+            Rcode::NXRRSet => write!(f, "NXRRSET"),
+            Rcode::NotAuth => write!(f, "NOTAUTH"),
+            Rcode::NotZone => write!(f, "NOTZONE"),
+            Rcode::DSOTypeNI => write!(f, "DSOTYPENI"),
+            Rcode::BadVers => write!(f, "BADVERS"),
+            Rcode::BadKey => write!(f, "BADKEY"),
+            Rcode::BadTime => write!(f, "BADTIME"),
+            Rcode::BadMode => write!(f, "BADMODE"),
+            Rcode::BadName => write!(f, "BADNAME"),
+            Rcode::BadAlg => write!(f, "BADALG"),
+            Rcode::BadTrunc => write!(f, "BADTRUNC"),
+            Rcode::BadCookie => write!(f, "BADCOOKIE"),
+            Rcode::Unknown(x) => write!(f, "RCODE{}", x),
+        }
+    }
+}
+
+impl Rcode {
+    /// Returns the the Enum value
+    pub fn value(&self) -> u16 {
+        match *self {
+            Rcode::NoError => 0,
+            Rcode::FormErr => 1,
+            Rcode::ServFail => 2,
+            Rcode::NXDomain => 3,
+            Rcode::NotImp => 4,
+            Rcode::Refused => 5,
+            Rcode::YXDomain => 6,
+            Rcode::YXRRSet => 7,
+            Rcode::NXRRSet => 8,
+            Rcode::NotAuth => 9,
+            Rcode::NotZone => 10,
+            Rcode::DSOTypeNI => 11,
+            Rcode::BadVers => 16,
+            Rcode::BadKey => 17,
+            Rcode::BadTime => 18,
+            Rcode::BadMode => 19,
+            Rcode::BadName => 20,
+            Rcode::BadAlg => 21,
+            Rcode::BadTrunc => 22,
+            Rcode::BadCookie => 23,
+            Rcode::Unknown(x) => x,
+        }
+    }
+
+    /// Returns Enum variant for given value.
+    /// Values without an own variant become Unknown.
+    ///
+    /// # Arguments
+    /// `value` - actual Rcode value
+    pub fn get_rcode(value: u16) -> Self {
+        match value {
+            0 => Rcode::NoError,
+            1 => Rcode::FormErr,
+            2 => Rcode::ServFail,
+            3 => Rcode::NXDomain,
+            4 => Rcode::NotImp,
+            5 => Rcode::Refused,
+            6 => Rcode::YXDomain,
+            7 => Rcode::YXRRSet,
+            8 => Rcode::NXRRSet,
+            9 => Rcode::NotAuth,
+            10 => Rcode::NotZone,
+            11 => Rcode::DSOTypeNI,
+            16 => Rcode::BadVers,
+            17 => Rcode::BadKey,
+            18 => Rcode::BadTime,
+            19 => Rcode::BadMode,
+            20 => Rcode::BadName,
+            21 => Rcode::BadAlg,
+            22 => Rcode::BadTrunc,
+            23 => Rcode::BadCookie,
+            x => Rcode::Unknown(x),
+        }
+    }
+
+    /// Returns the Rcode combined from the 4 bits of the header
+    /// and the upper 8 bits from the OPT record.
+    ///
+    /// # Arguments
+    /// `header` - the RCODE of the header
+    /// `extended` - the extended RCODE of the OPT record, zero without EDNS
+    pub fn from_parts(header: u8, extended: u8) -> Self {
+        Rcode::get_rcode(u16::from(extended) << 4 | u16::from(header & 0x0f))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_rcode() {
+        assert_eq!(Rcode::get_rcode(3), Rcode::NXDomain);
+        assert_eq!(Rcode::get_rcode(3).to_string(), "NXDOMAIN");
+        assert_eq!(Rcode::get_rcode(12), Rcode::Unknown(12));
+        assert_eq!(Rcode::BadCookie.value(), 23);
+    }
+
+    #[test]
+    fn test_from_parts() {
+        assert_eq!(Rcode::from_parts(2, 0), Rcode::ServFail);
+        assert_eq!(Rcode::from_parts(0, 1), Rcode::BadVers);
+        assert_eq!(Rcode::from_parts(7, 1), Rcode::BadCookie);
+    }
+}
@@ -0,0 +1,274 @@
+use crate::class::Class;
+use crate::domainname::DomainName;
+use crate::qtype::Qtype;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::vec::Vec;
+
+/// The data of a record, depending on its type
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, PartialEq, Clone)]
+pub enum RData {
+    /// IPv4 adress
+    A(Ipv4Addr),
+    /// IPv6 adress
+    AAAA(Ipv6Addr),
+    /// authoritative name server
+    NS(DomainName),
+    /// canonical name
+    CNAME(DomainName),
+    /// start of a zone of authority
+    SOA {
+        mname: DomainName,
+        rname: DomainName,
+        serial: u32,
+        refresh: u32,
+        retry: u32,
+        expire: u32,
+        minimum: u32,
+    },
+    /// domain name pointer
+    PTR(DomainName),
+    /// mail exchange
+    MX {
+        preference: u16,
+        exchange: DomainName,
+    },
+    /// character-strings, they don't have to be valid UTF-8
+    TXT(Vec<Vec<u8>>),
+    /// service location
+    SRV {
+        priority: u16,
+        weight: u16,
+        port: u16,
+        target: DomainName,
+    },
+    /// raw data of a type without an own variant
+    Unknown(Vec<u8>),
+}
+
+impl fmt::Display for RData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            RData::A(ip) => write!(f, "{}", ip),
+            RData::AAAA(ip) => write!(f, "{}", ip),
+            RData::NS(name) | RData::CNAME(name) | RData::PTR(name) => write!(f, "{}", name),
+            RData::SOA {
+                mname,
+                rname,
+                serial,
+                refresh,
+                retry,
+                expire,
+                minimum,
+            } => write!(
+                f,
+                "{} {} {} {} {} {} {}",
+                mname, rname, serial, refresh, retry, expire, minimum
+            ),
+            RData::MX {
+                preference,
+                exchange,
+            } => write!(f, "{} {}", preference, exchange),
+            RData::TXT(strings) => write!(
+                f,
+                "{}",
+                strings
+                    .iter()
+                    .map(|s| format!("\"{}\"", escape_character_string(s)))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            RData::SRV {
+                priority,
+                weight,
+                port,
+                target,
+            } => write!(f, "{} {} {} {}", priority, weight, port, target),
+            RData::Unknown(data) => write!(f, "{}", format_unknown(data)),
+        }
+    }
+}
+
+/// A resource record
+#[derive(Debug, PartialEq, Clone)]
+pub struct Record {
+    /// The owner of the record
+    pub name: DomainName,
+    pub rtype: Qtype,
+    pub class: Class,
+    /// Seconds the record may be cached
+    pub ttl: u32,
+    pub rdata: RData,
+}
+
+impl Record {
+    /// Returns a new Record
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The owner of the record
+    /// * `rtype` - The type, it tells how the rdata is built
+    /// * `class` - The class, usually IN
+    /// * `ttl` - Seconds the record may be cached
+    /// * `rdata` - The data
+    pub fn new(name: DomainName, rtype: Qtype, class: Class, ttl: u32, rdata: RData) -> Self {
+        Record {
+            name,
+            rtype,
+            class,
+            ttl,
+            rdata,
+        }
+    }
+
+    /// Returns the adress of an A or AAAA record
+    pub fn ip(&self) -> Option<IpAddr> {
+        match self.rdata {
+            RData::A(ip) => Some(IpAddr::V4(ip)),
+            RData::AAAA(ip) => Some(IpAddr::V6(ip)),
+            _ => None,
+        }
+    }
+
+    /// Returns the preference of a MX record, the priority of a SRV record or None for other records
+    pub fn preference(&self) -> Option<u16> {
+        match self.rdata {
+            RData::MX { preference, .. } => Some(preference),
+            RData::SRV { priority, .. } => Some(priority),
+            _ => None,
+        }
+    }
+}
+
+/// Escapes a character-string the way dig prints it.
+/// Printable ASCII is kept, quotes and backslashes get a leading backslash
+/// and every other byte is written as backslash followed by three decimal digits.
+///
+/// Example: a"b\n -> a\"b\010
+///
+/// # Arguments
+///
+/// * `bytes` - The content of the character-string
+pub fn escape_character_string(bytes: &[u8]) -> String {
+    let mut escaped = String::with_capacity(bytes.len());
+    for &b in bytes {
+        match b {
+            b'"' | b'\\' => {
+                escaped.push('\\');
+                escaped.push(b as char);
+            }
+            0x20..=0x7e => escaped.push(b as char),
+            _ => escaped.push_str(&format!("\\{:03}", b)),
+        }
+    }
+    escaped
+}
+
+/// Formats a TTL the way BIND prints it with human readable units.
+/// Units that are zero are left out, a TTL of zero is "0s".
+///
+/// Example: 3720 -> 1h2m
+///
+/// # Arguments
+///
+/// * `ttl` - The TTL in seconds
+pub fn format_ttl_human(ttl: u32) -> String {
+    if ttl == 0 {
+        return String::from("0s");
+    }
+    let units = [
+        (604_800, 'w'),
+        (86_400, 'd'),
+        (3_600, 'h'),
+        (60, 'm'),
+        (1, 's'),
+    ];
+    let mut rest = ttl;
+    let mut formatted = String::new();
+    for &(seconds, unit) in units.iter() {
+        if rest >= seconds {
+            formatted.push_str(&format!("{}{}", rest / seconds, unit));
+            rest %= seconds;
+        }
+    }
+    formatted
+}
+
+/// Formats the RDATA of a record of unknown type like RFC 3597 describes it:
+/// `\#` followed by the length and the data in hex.
+///
+/// Example: `\# 4 0a000001`
+///
+/// # Arguments
+///
+/// * `rdata` - The RDATA of the record
+fn format_unknown(rdata: &[u8]) -> String {
+    if rdata.is_empty() {
+        String::from("\\# 0")
+    } else {
+        let hex = rdata
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        format!("\\# {} {}", rdata.len(), hex)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_character_string() {
+        assert_eq!(
+            escape_character_string(b"v=spf1 \"a\\b\"\n\xff"),
+            "v=spf1 \\\"a\\\\b\\\"\\010\\255"
+        );
+    }
+
+    #[test]
+    fn test_format_ttl_human() {
+        assert_eq!(format_ttl_human(3720), "1h2m");
+        assert_eq!(format_ttl_human(0), "0s");
+        assert_eq!(format_ttl_human(59), "59s");
+        assert_eq!(format_ttl_human(694_861), "1w1d1h1m1s");
+    }
+
+    #[test]
+    fn test_format_unknown() {
+        assert_eq!(format_unknown(&[10, 0, 0, 1]), "\\# 4 0a000001");
+        assert_eq!(format_unknown(&[]), "\\# 0");
+    }
+
+    #[test]
+    fn test_rdata_display() {
+        let mx = RData::MX {
+            preference: 0,
+            exchange: ".".parse().unwrap(),
+        };
+        assert_eq!(mx.to_string(), "0 .");
+        let txt = RData::TXT(vec![b"a b".to_vec(), vec![7]]);
+        assert_eq!(txt.to_string(), "\"a b\" \"\\007\"");
+    }
+
+    #[test]
+    fn test_record_preference() {
+        let srv = RData::SRV {
+            priority: 10,
+            weight: 5,
+            port: 443,
+            target: "example.com.".parse().unwrap(),
+        };
+        let record = Record::new(
+            "_https._tcp.example.com".parse().unwrap(),
+            Qtype::SRV,
+            Class::IN,
+            60,
+            srv,
+        );
+        assert_eq!(record.preference(), Some(10));
+        assert_eq!(record.ip(), None);
+        assert_eq!(record.rdata.to_string(), "10 5 443 example.com");
+    }
+}
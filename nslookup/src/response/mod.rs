@@ -1,39 +1,40 @@
-use crate::customerror::CustomError;
+use crate::class::Class;
+use crate::customerror::{CustomError, ParseReason};
+use crate::domainname::{DomainName, MAX_NAME_LENGTH};
+use crate::edns::{get_options, Edns};
+use crate::header::Header;
+use crate::idna;
 use crate::qtype::Qtype;
+use crate::question::Question;
+use crate::rcode::Rcode;
+use crate::record::{format_ttl_human, RData, Record};
 use std::fmt;
-use std::net::IpAddr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::vec::Vec;
 
+/// The maximum number of compression pointers followed in one domain
+const MAX_POINTER_HOPS: usize = 16;
+
 /// A struct that holds the Response
 #[derive(PartialEq)]
 pub struct Response {
-    pub domain: String,
-    pub ip: Vec<Ip>,
+    pub domain: DomainName,
+    pub answers: Vec<Record>,
+    /// Records of the authority section, e.g. the SOA of the zone if the answer is empty
+    pub authority: Vec<Record>,
+    /// The result of the query, including the extended bits of EDNS
+    pub rcode: Rcode,
 }
 
 impl fmt::Display for Response {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        let mut res_str = String::new();
-        for ip in &self.ip {
-            res_str.push_str(&ip.qtype.to_string());
-            res_str.push_str(": ");
-            res_str.push_str(&ip.ip);
-            res_str.push_str("\n");
-        }
-        write!(f, "Domain: {}\nAdress(es):\n{}", self.domain, res_str)
+        write!(f, "{}", self.format(false))
     }
 }
 
 impl fmt::Debug for Response {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        let mut res_str = String::new();
-        for ip in &self.ip {
-            res_str.push_str(&ip.qtype.to_string());
-            res_str.push_str(": ");
-            res_str.push_str(&ip.ip);
-            res_str.push_str("\n");
-        }
-        write!(f, "Domain: {}\nAdress(es):\n{}", self.domain, res_str)
+        write!(f, "{}", self.format(false))
     }
 }
 
@@ -43,55 +44,216 @@ impl Response {
     /// # Arguments
     ///
     /// * `domain` - The domain
-    /// * `ip` - A Vector of IP Structs
-    pub fn new(domain: String, ip: Vec<Ip>) -> Self {
-        Response { domain, ip }
+    /// * `answers` - The records of the answer section
+    pub fn new(domain: DomainName, answers: Vec<Record>) -> Self {
+        Response {
+            domain,
+            answers,
+            authority: vec![],
+            rcode: Rcode::NoError,
+        }
+    }
+
+    /// Returns the adresses of all A and AAAA records of the answer
+    pub fn ips(&self) -> Vec<IpAddr> {
+        self.answers.iter().filter_map(Record::ip).collect()
+    }
+
+    /// Returns true if the name exists but has no records of the asked type (NODATA).
+    /// The server answered NOERROR without answers, usually with the SOA of the zone as authority.
+    pub fn is_nodata(&self) -> bool {
+        self.rcode == Rcode::NoError && self.answers.is_empty()
+    }
+
+    /// Returns all records of the answer with the given type
+    ///
+    /// # Arguments
+    ///
+    /// * `rtype` - The type of the records
+    pub fn records(&self, rtype: Qtype) -> Vec<&Record> {
+        self.answers.iter().filter(|r| r.rtype == rtype).collect()
+    }
+
+    /// Returns the Response as it is printed, one record per line with its TTL.
+    /// The class is only printed if it isn't IN.
+    /// An internationalized domain is printed in Unicode followed by the ASCII form.
+    ///
+    /// # Arguments
+    ///
+    /// * `ttl_human` - Print the TTL with units (e.g. 1h2m) instead of seconds
+    pub fn format(&self, ttl_human: bool) -> String {
+        let mut res_str = String::new();
+        for record in self.sorted() {
+            res_str.push_str(&format_record(record, ttl_human));
+        }
+        if self.answers.is_empty() && !self.authority.is_empty() {
+            res_str.push_str("Authority:\n");
+            for record in &self.authority {
+                res_str.push_str(&format_record(record, ttl_human));
+            }
+        }
+        let domain = self.domain.to_string();
+        let unicode = idna::to_unicode(&domain);
+        if unicode == domain {
+            format!("Domain: {}\nAdress(es):\n{}", domain, res_str)
+        } else {
+            format!("Domain: {} ({})\nAdress(es):\n{}", unicode, domain, res_str)
+        }
+    }
+
+    /// Returns the records of the answer in the order they are printed.
+    /// MX and SRV records are sorted by their preference, lowest first, after all other records.
+    pub fn sorted(&self) -> Vec<&Record> {
+        let mut sorted = self.answers.iter().collect::<Vec<_>>();
+        sorted.sort_by_key(|record| record.preference());
+        sorted
     }
 
     /// Tries to parse an DNS-Answer into a Response.
-    /// It checks the status of the response.
-    /// If it's ok it parses the Domain and Ip(s) from it.
+    /// It checks that the package is a response.
+    /// If it's ok it parses the Domain, the Rcode and Records from it.
+    /// If there is no answer the Domain is taken from the question.
+    /// A Rcode other than NoError is not an error here, it's returned on the Response.
     ///
     /// # Arguments
     ///
     /// * `buf` - The DNS-Answer
-    /// * `response_start_index` - The index where the actual respons(es) starts
-    pub fn parse_response(buf: &[u8], response_start_index: usize) -> Result<Self, CustomError> {
-        if response_start_index >= buf.len() {
+    pub fn parse_response(buf: &[u8]) -> Result<Self, CustomError> {
+        Header::parse(buf)?;
+        check_response_status(buf[2])?;
+        Response::from_message(Message::parse(buf)?)
+    }
+
+    /// Returns the Response of an already parsed message.
+    /// A successful message has to contain at least an answer or an authority record.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The parsed DNS-Message
+    pub fn from_message(message: Message) -> Result<Self, CustomError> {
+        let rcode = message.rcode();
+        if rcode == Rcode::NoError && message.answers.is_empty() && message.authority.is_empty() {
             return Err(CustomError::EmptyResponse);
         }
-        check_response_status(buf[2], buf[3])?;
-        let domain_index_raw =
-            combine_u8tou16(buf[response_start_index], buf[response_start_index + 1]);
+        let domain = match (message.answers.first(), message.questions.first()) {
+            (Some(record), _) => record.name.clone(),
+            (None, Some(question)) => question.name.clone(),
+            (None, None) => DomainName::root(),
+        };
+
+        Ok(Response {
+            domain,
+            answers: message.answers,
+            authority: message.authority,
+            rcode,
+        })
+    }
+
+    /// Returns the Response if the Rcode is NoError, else the Rcode as error
+    pub fn check_rcode(self) -> Result<Self, CustomError> {
+        match self.rcode {
+            Rcode::NoError => Ok(self),
+            rcode => Err(CustomError::Rcode(rcode)),
+        }
+    }
+
+    /// Returns one Response for the replies to all questions of a name, e.g. its A and AAAA question.
+    /// The records are kept in the order of the replies, a record that is in several replies
+    /// (e.g. the CNAME of the name) is kept once.
+    /// Replies that are empty or have an error are skipped. If no reply is left,
+    /// the first error that isn't just an empty response is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `replies` - The DNS-Answers in the order the questions were asked
+    pub fn from_replies(replies: &[Vec<u8>]) -> Result<Self, CustomError> {
+        let mut combined: Option<Response> = None;
+        let mut error = None;
+        for reply in replies {
+            match Response::parse_response(reply).and_then(Response::check_rcode) {
+                Ok(response) => match combined.as_mut() {
+                    Some(combined) => combined.merge(response),
+                    None => combined = Some(response),
+                },
+                Err(CustomError::EmptyResponse) => {}
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        combined.ok_or_else(|| error.unwrap_or(CustomError::EmptyResponse))
+    }
 
-        Ok(Response::new(
-            get_domain_r(&buf, get_name_index(domain_index_raw))?,
-            get_ip_r(&buf, response_start_index)?,
-        ))
+    /// Appends the records of another Response that aren't in this one yet
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The Response of another question for the same name
+    fn merge(&mut self, other: Response) {
+        for record in other.answers {
+            if !self.answers.contains(&record) {
+                self.answers.push(record);
+            }
+        }
+        for record in other.authority {
+            if !self.authority.contains(&record) {
+                self.authority.push(record);
+            }
+        }
     }
 }
 
-/// A struct that holds an Ip
+/// A complete DNS-Message with all four sections
 #[derive(Debug, PartialEq)]
-pub struct Ip {
-    pub ip: String,
-    pub qtype: Qtype,
+pub struct Message {
+    pub header: Header,
+    pub questions: Vec<Question>,
+    pub answers: Vec<Record>,
+    pub authority: Vec<Record>,
+    /// The additional section without the OPT record
+    pub additional: Vec<Record>,
+    /// The OPT record, if the sender supports EDNS
+    pub edns: Option<Edns>,
 }
 
-impl Ip {
-    /// Returns a new Ip
+impl Message {
+    /// Parses a DNS-Message.
+    /// The header counts tell how many entries every section has,
+    /// so the original query isn't needed.
     ///
     /// # Arguments
     ///
-    /// * `ip` - The adress
-    /// * `qtype` - The query Type
-    pub fn new(ip: String, qtype: Qtype) -> Self {
-        Ip { ip, qtype }
+    /// * `buf` - The DNS-Message
+    pub fn parse(buf: &[u8]) -> Result<Self, CustomError> {
+        let header = Header::parse(buf)?;
+        let (questions, index) = get_questions(buf, 12, header.qdcount)?;
+        let (answers, index) = get_records(buf, index, header.ancount)?;
+        let (authority, index) = get_records(buf, index, header.nscount)?;
+        let (mut additional, _) = get_records(buf, index, header.arcount)?;
+        let edns = match additional.iter().position(|r| r.rtype == Qtype::OPT) {
+            Some(position) => Some(Edns::from_record(&additional.remove(position))?),
+            None => None,
+        };
+
+        Ok(Message {
+            header,
+            questions,
+            answers,
+            authority,
+            additional,
+            edns,
+        })
+    }
+
+    /// Returns the Rcode of the message.
+    /// The upper 8 bits of an extended Rcode are in the OPT record.
+    pub fn rcode(&self) -> Rcode {
+        let extended = self.edns.as_ref().map_or(0, |edns| edns.extended_rcode);
+        Rcode::from_parts(self.header.rcode, extended)
     }
 }
 
-/// Checks a) if the given DNS-Package is acutally a response
-/// and b) if errors occured.
+/// Checks if the given DNS-Package is acutally a response.
 ///
 ///+++++++++flag1++++++++++++++++++++flag2+++++++++++++
 /// 7  6  5  4  3  2  1     0  7  6  5  4  3  2  1  0
@@ -99,25 +261,41 @@ impl Ip {
 ///|QR|   Opcode  |AA|TC|  |RD|RA|   Z    |   RCODE   |
 ///
 /// QR specifies wether this is a message(0) or response(1). We only want responses.
-/// RCODE contains errors and all must be zero.
+/// Errors in RCODE are returned as Rcode on the Response.
 ///
 /// # Arguments
 ///
 /// * `flags1` - Contains the flags
-/// * `flags2` - Contains the flags
-fn check_response_status(flags1: u8, flags2: u8) -> Result<(), CustomError> {
-    if !bit_at(flags1, 7)
-        || bit_at(flags2, 3)
-        || bit_at(flags2, 2)
-        || bit_at(flags2, 1)
-        || bit_at(flags2, 0)
-    {
+fn check_response_status(flags1: u8) -> Result<(), CustomError> {
+    if !bit_at(flags1, 7) {
         Err(CustomError::ResponseError)
     } else {
         Ok(())
     }
 }
 
+/// Returns a single line of the Response output
+///
+/// # Arguments
+///
+/// * `record` - The record
+/// * `ttl_human` - Print the TTL with units instead of seconds
+pub fn format_record(record: &Record, ttl_human: bool) -> String {
+    let ttl = if ttl_human {
+        format_ttl_human(record.ttl)
+    } else {
+        record.ttl.to_string()
+    };
+    if record.class == Class::IN {
+        format!("{}: {} (ttl {})\n", record.rtype, record.rdata, ttl)
+    } else {
+        format!(
+            "{} {}: {} (ttl {})\n",
+            record.rtype, record.class, record.rdata, ttl
+        )
+    }
+}
+
 /// Returns the value of a single bit in an u8
 ///
 /// # Arguments
@@ -144,108 +322,347 @@ fn bit_at(input: u8, n: u8) -> bool {
 /// field[8] contains length 3
 /// ...
 ///
+/// Instead of a length the field may contain a compression pointer (two leading 1)
+/// to the rest of the domain somewhere earlier in the package.
+/// The labels are kept as they are, so a label may contain a dot or bytes that aren't printable.
+///
 /// # Arguments
 ///
 /// * `response` - The complete DNS-Package
 /// * `index` - The index where the first length of the domain is located (this is in the question section)
-fn get_domain_r(response: &[u8], index: usize) -> Result<String, CustomError> {
-    let length = response[index] as usize;
-    let startindex = index + 1;
-    let next = startindex + length;
-
-    if next < response.len() {
-        let domain_build = (startindex..next)
-            .map(|a| response[a] as char)
-            .collect::<String>();
-
-        if response[next] == 0 {
-            Ok(domain_build)
-        } else {
-            Ok(format!(
-                "{}.{}",
-                domain_build,
-                get_domain_r(response, next)?
-            ))
+fn get_domain_r(response: &[u8], index: usize) -> Result<DomainName, CustomError> {
+    let mut labels = vec![];
+    get_labels_r(response, index, &mut vec![], &mut labels)?;
+    DomainName::from_labels(labels)
+}
+
+/// Collects the labels of a domain recursively like RFC 1035 4.1.4 describes it.
+/// A compression pointer may appear after any number of labels, it ends the labels at this position.
+/// Pointers have to point backwards, every target may only be visited once
+/// and at most MAX_POINTER_HOPS pointers are followed.
+/// The complete name may not be longer than MAX_NAME_LENGTH on the wire.
+///
+/// # Arguments
+///
+/// * `response` - The complete DNS-Package
+/// * `index` - The index of the next length or pointer
+/// * `pointers` - The targets of the pointers followed so far
+/// * `labels` - The labels found so far
+fn get_labels_r(
+    response: &[u8],
+    index: usize,
+    pointers: &mut Vec<usize>,
+    labels: &mut Vec<Vec<u8>>,
+) -> Result<(), CustomError> {
+    let length = get_u8(response, index)?;
+    match length & 0xC0 {
+        0xC0 => {
+            let pointer = get_name_index(get_u16(response, index)?);
+            let reason = if pointers.contains(&pointer) {
+                ParseReason::PointerLoop
+            } else if pointer >= index {
+                ParseReason::ForwardPointer
+            } else if pointers.len() == MAX_POINTER_HOPS {
+                ParseReason::TooManyPointers
+            } else {
+                pointers.push(pointer);
+                return get_labels_r(response, pointer, pointers, labels);
+            };
+            Err(CustomError::Malformed(index, reason))
         }
-    } else {
-        Err(CustomError::Overflow)
+        0x00 if length == 0 => Ok(()),
+        0x00 => {
+            let length = length as usize;
+            // every label has a length octet, the root label is one more octet
+            let wire_length = labels.iter().map(|l| l.len() + 1).sum::<usize>() + length + 2;
+            if wire_length > MAX_NAME_LENGTH {
+                return Err(CustomError::Malformed(index, ParseReason::NameTooLong));
+            }
+            let label = get_slice(response, index + 1, length)?;
+            labels.push(label.to_vec());
+            get_labels_r(response, index + 1 + length, pointers, labels)
+        }
+        _ => Err(CustomError::Malformed(index, ParseReason::InvalidLabelType)),
     }
 }
 
-/// Gets the Adresses from one or more answers recursively.
-/// The answers are concatenated together.
-/// An answer contains a query type from which you can know which type of adress the answer contains
-/// and of course the adress itself.
-/// The function runs until there are no answers left.
-/// If a unsupported query type occurs the function throws an error.
+/// Returns the index right after a domain.
+/// The domain either ends with zero or with a compression pointer.
 ///
 /// # Arguments
 ///
 /// * `response` - The complete DNS-Package
-/// * `answer_start_index` - The index where the first actual answer begins
-fn get_ip_r(response: &[u8], answer_start_index: usize) -> Result<Vec<Ip>, CustomError> {
-    if answer_start_index + 12 >= response.len() {
-        return Err(CustomError::Overflow);
-    }
-
-    let mut result = vec![];
-
-    let qtypehex = combine_u8tou16(
-        response[answer_start_index + 2],
-        response[answer_start_index + 3],
-    ) as usize;
-    let qtype = Qtype::get_qtype(qtypehex)?;
-
-    let ip_length = combine_u8tou16(
-        response[answer_start_index + 10],
-        response[answer_start_index + 11],
-    ) as usize;
-    let ip_start_index = answer_start_index + 12;
-
-    match qtype {
-        Qtype::A | Qtype::AAAA => result.push(Ip::new(
-            format_ip(&response[ip_start_index..ip_start_index + ip_length])?,
-            qtype,
-        )),
-        Qtype::CNAME => {
-            if let Ok(cname) =
-                get_domain_r(&response[ip_start_index..ip_start_index + ip_length], 0)
-            {
-                result.push(Ip::new(cname, qtype))
+/// * `index` - The index where the first length of the domain is located
+fn skip_name(response: &[u8], index: usize) -> Result<usize, CustomError> {
+    let mut index = index;
+    loop {
+        match get_u8(response, index)? {
+            0 => return Ok(index + 1),
+            length if length & 0xC0 == 0xC0 => {
+                get_u8(response, index + 1)?;
+                return Ok(index + 2);
+            }
+            length if length & 0xC0 != 0 => {
+                return Err(CustomError::Malformed(index, ParseReason::InvalidLabelType))
             }
+            length => index += 1 + length as usize,
         }
     }
+}
 
-    let next = ip_start_index + ip_length;
-    if next >= response.len() {
-        Ok(result)
+/// Returns the index right after a domain inside of RDATA.
+/// The domain has to end before the RDATA ends.
+///
+/// # Arguments
+///
+/// * `response` - The complete DNS-Package
+/// * `index` - The index where the first length of the domain is located
+/// * `end` - The index right after the RDATA
+fn skip_name_in(response: &[u8], index: usize, end: usize) -> Result<usize, CustomError> {
+    let next = skip_name(response, index)?;
+    if next > end {
+        Err(CustomError::Malformed(index, ParseReason::RdataLength))
     } else {
-        result.append(&mut get_ip_r(response, next)?);
-        Ok(result)
+        Ok(next)
     }
 }
 
+/// Gets the Questions of the question section.
+/// Returns them together with the index right after the last one.
+///
+/// # Arguments
+///
+/// * `response` - The complete DNS-Package
+/// * `index` - The index where the first question begins
+/// * `count` - The number of questions
+pub(crate) fn get_questions(
+    response: &[u8],
+    mut index: usize,
+    count: u16,
+) -> Result<(Vec<Question>, usize), CustomError> {
+    let mut questions = vec![];
+    for _ in 0..count {
+        let fields_index = skip_name(response, index)?;
+        let qtype = Qtype::get_qtype(get_u16(response, fields_index)? as usize)?;
+        let class = Class::get_class(get_u16(response, fields_index + 2)?);
+        questions
+            .push(Question::from_name(get_domain_r(response, index)?, qtype).with_class(class));
+        index = fields_index + 4;
+    }
+    Ok((questions, index))
+}
+
+/// Gets the Records of a section.
+/// The records are concatenated together.
+/// A record contains the owner, a type from which you can know how the data of the record is built,
+/// the class, the ttl and of course the data itself.
+/// Returns the Records together with the index right after the last one.
+/// The records are read in a loop, so a hostile count can't exhaust the stack.
+///
+/// # Arguments
+///
+/// * `response` - The complete DNS-Package
+/// * `index` - The index where the first record begins
+/// * `count` - The number of records in the section
+fn get_records(
+    response: &[u8],
+    mut index: usize,
+    count: u16,
+) -> Result<(Vec<Record>, usize), CustomError> {
+    let mut records = vec![];
+    for _ in 0..count {
+        let fields_index = skip_name(response, index)?;
+        let name = get_domain_r(response, index)?;
+        let qtype = Qtype::get_qtype(get_u16(response, fields_index)? as usize)?;
+        let class = Class::get_class(get_u16(response, fields_index + 2)?);
+        let ttl = get_u32(response, fields_index + 4)?;
+        let rdata_length = get_u16(response, fields_index + 8)? as usize;
+        let rdata_index = fields_index + 10;
+
+        let rdata = get_rdata(response, rdata_index, rdata_length, qtype)?;
+        records.push(Record::new(name, qtype, class, ttl, rdata));
+        index = rdata_index + rdata_length;
+    }
+    Ok((records, index))
+}
+
+/// Parses the data of a record.
+/// Domains inside the data may be compressed, so the complete DNS-Package is needed.
+///
+/// # Arguments
+///
+/// * `response` - The complete DNS-Package
+/// * `index` - The index where the data begins
+/// * `length` - The length of the data
+/// * `qtype` - The type of the record
+fn get_rdata(
+    response: &[u8],
+    index: usize,
+    length: usize,
+    qtype: Qtype,
+) -> Result<RData, CustomError> {
+    let rdata = get_slice(response, index, length)?;
+    let end = index + length;
+    let too_short = |minimum: usize| {
+        if length < minimum {
+            Err(CustomError::Malformed(index, ParseReason::RdataLength))
+        } else {
+            Ok(())
+        }
+    };
+    // errors of the RDATA parsers are counted from the beginning of the RDATA
+    let in_packet = |e| match e {
+        CustomError::Malformed(offset, reason) => CustomError::Malformed(index + offset, reason),
+        e => e,
+    };
+    let rdata = match qtype {
+        Qtype::A | Qtype::AAAA => match format_ip(rdata) {
+            Ok(IpAddr::V4(ip)) if qtype == Qtype::A => RData::A(ip),
+            Ok(IpAddr::V6(ip)) if qtype == Qtype::AAAA => RData::AAAA(ip),
+            _ => return Err(CustomError::Malformed(index, ParseReason::RdataLength)),
+        },
+        Qtype::NS | Qtype::CNAME | Qtype::PTR => {
+            skip_name_in(response, index, end)?;
+            let name = get_domain_r(response, index)?;
+            match qtype {
+                Qtype::NS => RData::NS(name),
+                Qtype::CNAME => RData::CNAME(name),
+                _ => RData::PTR(name),
+            }
+        }
+        Qtype::SOA => {
+            let rname_index = skip_name_in(response, index, end)?;
+            let serial_index = skip_name_in(response, rname_index, end)?;
+            if serial_index + 20 > end {
+                return Err(CustomError::Malformed(
+                    serial_index,
+                    ParseReason::RdataLength,
+                ));
+            }
+            RData::SOA {
+                mname: get_domain_r(response, index)?,
+                rname: get_domain_r(response, rname_index)?,
+                serial: get_u32(response, serial_index)?,
+                refresh: get_u32(response, serial_index + 4)?,
+                retry: get_u32(response, serial_index + 8)?,
+                expire: get_u32(response, serial_index + 12)?,
+                minimum: get_u32(response, serial_index + 16)?,
+            }
+        }
+        Qtype::MX => {
+            too_short(3)?;
+            skip_name_in(response, index + 2, end)?;
+            RData::MX {
+                preference: get_u16(response, index)?,
+                exchange: get_domain_r(response, index + 2)?,
+            }
+        }
+        Qtype::SRV => {
+            too_short(7)?;
+            skip_name_in(response, index + 6, end)?;
+            RData::SRV {
+                priority: get_u16(response, index)?,
+                weight: get_u16(response, index + 2)?,
+                port: get_u16(response, index + 4)?,
+                target: get_domain_r(response, index + 6)?,
+            }
+        }
+        Qtype::TXT => RData::TXT(get_character_strings(rdata).map_err(in_packet)?),
+        Qtype::OPT => {
+            // the options are read by Edns::from_record, an error is found here to get its offset in the package
+            get_options(rdata).map_err(in_packet)?;
+            RData::Unknown(rdata.to_vec())
+        }
+        Qtype::AXFR | Qtype::Unknown(_) => RData::Unknown(rdata.to_vec()),
+    };
+    Ok(rdata)
+}
+
+/// Splits the RDATA of a TXT record into its character-strings.
+/// Every character-string starts with a length byte followed by that many bytes.
+/// The bytes are returned as is because they don't have to be valid UTF-8.
+/// The offset of an error is counted from the beginning of the RDATA.
+///
+/// # Arguments
+///
+/// * `rdata` - The RDATA of the record
+pub fn get_character_strings(rdata: &[u8]) -> Result<Vec<Vec<u8>>, CustomError> {
+    let mut strings = vec![];
+    let mut index = 0;
+    while index < rdata.len() {
+        let length = get_u8(rdata, index)? as usize;
+        strings.push(get_slice(rdata, index + 1, length)?.to_vec());
+        index += 1 + length;
+    }
+    Ok(strings)
+}
+
 /// Formats bytes to ip representation.
 ///
 /// # Arguments
 ///
 /// * `buf` - An byte array containing an ip in bytes sized 4 or 16.
-fn format_ip(buf: &[u8]) -> Result<String, CustomError> {
+fn format_ip(buf: &[u8]) -> Result<IpAddr, CustomError> {
     if buf.len() == 4 {
         let mut array = [0; 4];
         array.copy_from_slice(buf);
-        Ok(IpAddr::from(array).to_string())
+        Ok(IpAddr::V4(Ipv4Addr::from(array)))
     } else if buf.len() == 16 {
         let mut array = [0; 16];
         array.copy_from_slice(buf);
-        Ok(IpAddr::from(array).to_string())
+        Ok(IpAddr::V6(Ipv6Addr::from(array)))
     } else {
         Err(CustomError::IpParseError)
     }
 }
 
-/// The response uses a compressed format for the domain name.
-/// It's actually just a pointer into the question section.
+/// Returns the bytes at the index or an error if the package ends before.
+///
+/// # Arguments
+///
+/// * `response` - The complete DNS-Package
+/// * `index` - The index of the first byte
+/// * `length` - The number of bytes
+fn get_slice(response: &[u8], index: usize, length: usize) -> Result<&[u8], CustomError> {
+    response
+        .get(index..index + length)
+        .ok_or(CustomError::Malformed(index, ParseReason::Truncated))
+}
+
+/// Returns the byte at the index or an error if the package ends before.
+///
+/// # Arguments
+///
+/// * `response` - The complete DNS-Package
+/// * `index` - The index of the byte
+fn get_u8(response: &[u8], index: usize) -> Result<u8, CustomError> {
+    Ok(get_slice(response, index, 1)?[0])
+}
+
+/// Returns the two bytes at the index as u16.
+///
+/// # Arguments
+///
+/// * `response` - The complete DNS-Package
+/// * `index` - The index of the first byte
+fn get_u16(response: &[u8], index: usize) -> Result<u16, CustomError> {
+    let bytes = get_slice(response, index, 2)?;
+    Ok(combine_u8tou16(bytes[0], bytes[1]))
+}
+
+/// Returns the four bytes at the index as u32.
+///
+/// # Arguments
+///
+/// * `response` - The complete DNS-Package
+/// * `index` - The index of the first byte
+fn get_u32(response: &[u8], index: usize) -> Result<u32, CustomError> {
+    let bytes = get_slice(response, index, 4)?;
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// The response uses a compressed format for domain names.
+/// It's actually just a pointer to a domain or the rest of a domain earlier in the package.
 /// The pointer combined from two u8's contains two leading 1 which
 /// have to be shifted away to get the index.
 ///
@@ -271,18 +688,23 @@ fn combine_u8tou16(val1: u8, val2: u8) -> u16 {
 }
 
 #[cfg(test)]
+#[allow(
+    clippy::double_parens,
+    clippy::unnecessary_cast,
+    clippy::bool_assert_comparison
+)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_check_response_status() {
-        assert_eq!(check_response_status(129 as u8, 128 as u8).unwrap(), (()));
+        assert_eq!(check_response_status(129 as u8).unwrap(), (()));
     }
 
     #[test]
     #[should_panic]
     fn test_check_response_status2() {
-        assert_eq!(check_response_status(1 as u8, 128 as u8).unwrap(), (()));
+        assert_eq!(check_response_status(1 as u8).unwrap(), (()));
     }
 
     #[test]
@@ -300,27 +722,130 @@ mod tests {
         ];
         assert_eq!(
             String::from("google.com"),
-            get_domain_r(&response, 12).unwrap()
+            get_domain_r(&response, 12).unwrap().to_string()
         );
     }
 
     #[test]
-    fn test_get_ip_r() {
+    fn test_get_records() {
         let response: Vec<u8> = vec![
             0, 2, 129, 128, 0, 1, 0, 1, 0, 0, 0, 0, 6, 103, 111, 111, 103, 108, 101, 3, 99, 111,
             109, 0, 0, 28, 0, 1, 192, 12, 0, 28, 0, 1, 0, 0, 1, 19, 0, 16, 42, 0, 20, 80, 64, 1, 8,
             32, 0, 0, 0, 0, 0, 0, 32, 14,
         ];
-        let ip = Ip::new(String::from("2a00:1450:4001:820::200e"), Qtype::AAAA);
-        let ips = vec![ip];
-        assert_eq!(get_ip_r(&response, 28).unwrap(), ips);
+        let record = Record::new(
+            "google.com".parse().unwrap(),
+            Qtype::AAAA,
+            Class::IN,
+            275,
+            RData::AAAA("2a00:1450:4001:820::200e".parse().unwrap()),
+        );
+        assert_eq!(
+            get_records(&response, 28, 1).unwrap(),
+            (vec![record], response.len())
+        );
+    }
+
+    #[test]
+    fn test_get_domain_r_pointer() {
+        // alt1 followed by a pointer to gmail-smtp-in.l.google.com at index 41
+        let response: Vec<u8> = vec![
+            0, 2, 129, 128, 0, 1, 0, 2, 0, 0, 0, 0, 5, 103, 109, 97, 105, 108, 3, 99, 111, 109, 0,
+            0, 15, 0, 1, 192, 12, 0, 15, 0, 1, 0, 0, 1, 44, 0, 30, 0, 20, 13, 103, 109, 97, 105,
+            108, 45, 115, 109, 116, 112, 45, 105, 110, 1, 108, 6, 103, 111, 111, 103, 108, 101, 3,
+            99, 111, 109, 0, 192, 12, 0, 15, 0, 1, 0, 0, 1, 44, 0, 9, 0, 5, 4, 97, 108, 116, 49,
+            192, 41,
+        ];
+        assert_eq!(
+            get_domain_r(&response, 83).unwrap().to_string(),
+            String::from("alt1.gmail-smtp-in.l.google.com")
+        );
+        assert_eq!(
+            get_domain_r(&response, 27).unwrap().to_string(),
+            String::from("gmail.com")
+        );
+        // a pointer to itself
+        assert!(matches!(
+            get_domain_r(&[3, 99, 111, 109, 192, 4], 0),
+            Err(CustomError::Malformed(4, ParseReason::ForwardPointer))
+        ));
+        // a pointer back to the beginning of its own name
+        assert!(matches!(
+            get_domain_r(&[3, 99, 111, 109, 192, 0], 0),
+            Err(CustomError::Malformed(4, ParseReason::PointerLoop))
+        ));
+        // labels with the reserved leading bits 01 and 10
+        assert!(matches!(
+            get_domain_r(&[64, 0], 0),
+            Err(CustomError::Malformed(0, ParseReason::InvalidLabelType))
+        ));
+        assert!(skip_name(&[128, 0], 0).is_err());
+    }
+
+    #[test]
+    fn test_get_domain_r_pointer_chain() {
+        // every name is one label followed by a pointer to the previous one
+        let mut response = vec![1, 97, 0];
+        for i in 0..MAX_POINTER_HOPS {
+            let previous = if i == 0 { 0 } else { 4 * i - 1 };
+            response.extend_from_slice(&[1, 98, 192, previous as u8]);
+        }
+        let last = response.len() - 4;
+        assert_eq!(
+            get_domain_r(&response, last).unwrap().to_string(),
+            format!("{}a", "b.".repeat(MAX_POINTER_HOPS))
+        );
+        response.extend_from_slice(&[192, last as u8]);
+        assert!(matches!(
+            get_domain_r(&response, last + 4),
+            Err(CustomError::Malformed(_, ParseReason::TooManyPointers))
+        ));
+    }
+
+    #[test]
+    fn test_get_domain_r_too_long() {
+        // 4 labels of 63 bytes need 257 bytes on the wire
+        let mut response = vec![];
+        for _ in 0..4 {
+            response.push(63);
+            response.extend_from_slice(&[97; 63]);
+        }
+        response.push(0);
+        assert!(matches!(
+            get_domain_r(&response, 0),
+            Err(CustomError::Malformed(192, ParseReason::NameTooLong))
+        ));
+        // 3 labels of 63 and one of 61 bytes are exactly 255 bytes
+        response[192] = 61;
+        response[254] = 0;
+        assert_eq!(
+            get_domain_r(&response[..255], 0).unwrap().to_string().len(),
+            253
+        );
+    }
+
+    #[test]
+    fn test_get_character_strings() {
+        assert_eq!(
+            get_character_strings(&[2, 104, 105, 0, 1, 33]).unwrap(),
+            vec![b"hi".to_vec(), vec![], b"!".to_vec()]
+        );
+        assert!(get_character_strings(&[3, 104, 105]).is_err());
+    }
+
+    #[test]
+    fn test_skip_name() {
+        assert_eq!(skip_name(&[3, 99, 111, 109, 0, 1], 0).unwrap(), 5);
+        assert_eq!(skip_name(&[1, 97, 192, 12, 0], 0).unwrap(), 4);
+        assert_eq!(skip_name(&[0], 0).unwrap(), 1);
+        assert!(skip_name(&[3, 99, 111], 0).is_err());
     }
 
     #[test]
     fn test_format_ipv6() {
         let vec: Vec<u8> = vec![42, 0, 20, 80, 64, 12, 12, 0, 0, 0, 0, 0, 0, 0, 0, 139];
         assert_eq!(
-            format_ip(&vec).unwrap(),
+            format_ip(&vec).unwrap().to_string(),
             String::from("2a00:1450:400c:c00::8b")
         );
     }
@@ -0,0 +1,200 @@
+use crate::client;
+use crate::config::ResolverConfig;
+use crate::customerror::CustomError;
+use crate::header::Header;
+use crate::qtype::Qtype;
+use crate::question::{DnsMessageBuilder, Question};
+use crate::record::{RData, Record};
+use crate::response::Response;
+use std::vec::Vec;
+
+/// A target of a SRV record
+#[derive(Debug, PartialEq, Clone)]
+pub struct SrvTarget {
+    pub priority: u16,
+    pub weight: u16,
+    pub port: u16,
+    pub target: String,
+}
+
+impl SrvTarget {
+    /// Returns a new SrvTarget
+    ///
+    /// # Arguments
+    ///
+    /// * `priority` - Targets with a lower priority are contacted first
+    /// * `weight` - Relative weight for targets with the same priority
+    /// * `port` - The port of the service
+    /// * `target` - The host name of the target
+    pub fn new(priority: u16, weight: u16, port: u16, target: &str) -> Self {
+        SrvTarget {
+            priority,
+            weight,
+            port,
+            target: String::from(target),
+        }
+    }
+
+    /// Returns the SrvTarget of a parsed SRV record or None if the Record isn't a SRV record
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - A SRV record
+    pub fn from_record(record: &Record) -> Option<Self> {
+        match record.rdata {
+            RData::SRV {
+                priority,
+                weight,
+                port,
+                ref target,
+            } => Some(SrvTarget::new(priority, weight, port, &target.to_string())),
+            _ => None,
+        }
+    }
+}
+
+/// Looks up the SRV records of a service and returns the targets in the order they should be contacted.
+/// A single target "." means the service is decidedly not available, then no target is returned.
+///
+/// Example: _ldap._tcp.example.com
+///
+/// # Arguments
+///
+/// * `name` - The service name in the form _service._proto.name
+/// * `config` - The name servers to ask
+pub fn lookup_srv(name: &str, config: &ResolverConfig) -> Result<Vec<SrvTarget>, CustomError> {
+    let header = Header::new(client::random_id(), false, 0);
+    let messages =
+        DnsMessageBuilder::new(header, vec![Question::new(name, Qtype::SRV)?]).build_messages()?;
+
+    let mut targets = vec![];
+    for message in messages {
+        let reply = client::query(&message, config)?;
+        let response = Response::parse_response(&reply)?.check_rcode()?;
+        targets.extend(response.answers.iter().filter_map(SrvTarget::from_record));
+    }
+
+    if targets.len() == 1 && targets[0].target == "." {
+        return Ok(vec![]);
+    }
+    Ok(order_targets(targets, &mut |max| {
+        (client::random_u64() % (u64::from(max) + 1)) as u32
+    }))
+}
+
+/// Orders SRV targets like RFC 2782 describes it.
+/// Targets are grouped by priority, lowest first.
+/// Inside a group the next target is selected randomly, a target with a higher weight is more likely to be selected.
+/// Targets with weight zero have a very small chance to be selected before others.
+///
+/// # Arguments
+///
+/// * `targets` - The unordered targets
+/// * `random` - Returns a random number between zero and the given maximum, both inclusive
+pub fn order_targets(
+    mut targets: Vec<SrvTarget>,
+    random: &mut dyn FnMut(u32) -> u32,
+) -> Vec<SrvTarget> {
+    targets.sort_by_key(|t| t.priority);
+
+    let mut ordered = Vec::with_capacity(targets.len());
+    while !targets.is_empty() {
+        let priority = targets[0].priority;
+        let end = targets
+            .iter()
+            .position(|t| t.priority != priority)
+            .unwrap_or(targets.len());
+        let mut group = targets.drain(..end).collect::<Vec<_>>();
+        // weight zero targets are placed at the beginning
+        group.sort_by_key(|t| t.weight != 0);
+
+        while !group.is_empty() {
+            let sum = group.iter().map(|t| u32::from(t.weight)).sum::<u32>();
+            let selected = random(sum);
+            let mut running_sum = 0;
+            let index = group
+                .iter()
+                .position(|t| {
+                    running_sum += u32::from(t.weight);
+                    running_sum >= selected
+                })
+                .unwrap_or(group.len() - 1);
+            ordered.push(group.remove(index));
+        }
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class::Class;
+
+    #[test]
+    fn test_from_record() {
+        let srv = RData::SRV {
+            priority: 10,
+            weight: 60,
+            port: 5060,
+            target: "sip.example.com".parse().unwrap(),
+        };
+        let record = Record::new(
+            "_sip._tcp.example.com".parse().unwrap(),
+            Qtype::SRV,
+            Class::IN,
+            60,
+            srv,
+        );
+        assert_eq!(
+            SrvTarget::from_record(&record).unwrap(),
+            SrvTarget::new(10, 60, 5060, "sip.example.com")
+        );
+        let mx = RData::MX {
+            preference: 10,
+            exchange: "mail.example.com".parse().unwrap(),
+        };
+        let record = Record::new("example.com".parse().unwrap(), Qtype::MX, Class::IN, 60, mx);
+        assert_eq!(SrvTarget::from_record(&record), None);
+    }
+
+    #[test]
+    fn test_order_targets_priority() {
+        let targets = vec![
+            SrvTarget::new(20, 0, 1, "c"),
+            SrvTarget::new(10, 5, 1, "a"),
+            SrvTarget::new(10, 5, 1, "b"),
+        ];
+        // always selecting the first entry keeps the order inside a priority
+        let ordered = order_targets(targets, &mut |_| 0);
+        let names = ordered
+            .iter()
+            .map(|t| t.target.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_order_targets_weight() {
+        let targets = vec![
+            SrvTarget::new(10, 10, 1, "light"),
+            SrvTarget::new(10, 0, 1, "zero"),
+            SrvTarget::new(10, 90, 1, "heavy"),
+        ];
+        // selecting the maximum picks the last target of the running sum
+        let ordered = order_targets(targets, &mut |max| max);
+        let names = ordered
+            .iter()
+            .map(|t| t.target.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["heavy", "light", "zero"]);
+
+        // a number inside the weight of "light" picks it first
+        let targets = vec![
+            SrvTarget::new(10, 10, 1, "light"),
+            SrvTarget::new(10, 90, 1, "heavy"),
+        ];
+        let mut numbers = vec![5, 0].into_iter();
+        let ordered = order_targets(targets, &mut |_| numbers.next().unwrap());
+        assert_eq!(ordered[0].target, "light");
+    }
+}
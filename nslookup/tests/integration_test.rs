@@ -1,8 +1,23 @@
 #[cfg(test)]
 extern crate nslookup;
+use nslookup::class::Class;
+use nslookup::config::ResolverConfig;
+use nslookup::customerror::{CustomError, NameReason, ParseReason};
+use nslookup::edns::{ClientSubnet, Edns};
+use nslookup::header::Header;
 use nslookup::qtype::Qtype;
-use nslookup::question::{DnsMessageBuilder, Header, Question};
-use nslookup::response::{Ip, Response};
+use nslookup::question::{DnsMessageBuilder, Question};
+use nslookup::rcode::Rcode;
+use nslookup::record::{RData, Record};
+use nslookup::response::{Message, Response};
+use nslookup::srv::{lookup_srv, SrvTarget};
+use std::net::UdpSocket;
+use std::thread;
+
+/// Returns a Record of class IN
+fn record(name: &str, rtype: Qtype, ttl: u32, rdata: RData) -> Record {
+    Record::new(name.parse().unwrap(), rtype, Class::IN, ttl, rdata)
+}
 
 #[test]
 fn test_qtype() {
@@ -13,22 +28,37 @@ fn test_qtype() {
     assert_eq!(Qtype::get_qtype(28).unwrap().value(), Qtype::AAAA.value());
 
     assert_eq!(Qtype::get_qtype(5).unwrap().value(), Qtype::CNAME.value());
+    assert_eq!(Qtype::get_qtype(15).unwrap().value(), Qtype::MX.value());
+    assert!("mx".parse::<Qtype>().unwrap() == Qtype::MX);
+    assert!(Qtype::get_qtype(2).unwrap() == Qtype::NS);
+    assert!(Qtype::get_qtype(6).unwrap() == Qtype::SOA);
 }
 
 #[test]
 fn test_question() {
-    let header = Header::new(1, false, false);
+    let header = Header::new(1, false, 0);
     assert_eq!(
-        header.get_header().unwrap(),
+        header.get_header(),
         b"\x00\x01\x01\x00\x00\x01\x00\x00\x00\x00\x00\x00"
     );
 
-    let question = Question::new("example.com", Qtype::A);
+    let question = Question::new("example.com", Qtype::A).unwrap();
     assert_eq!(
         question.get_question(),
         b"\x07\x65\x78\x61\x6d\x70\x6c\x65\x03\x63\x6f\x6d\x00\x00\x01\x00\x01"
     );
 
+    // labels may contain escaped dots, empty labels are rejected
+    let escaped = Question::new("a\\.b.example.", Qtype::A).unwrap();
+    assert_eq!(
+        escaped.get_question(),
+        b"\x03a.b\x07example\x00\x00\x01\x00\x01"
+    );
+    assert!(matches!(
+        Question::new("a..b", Qtype::A),
+        Err(CustomError::InvalidName(_, NameReason::EmptyLabel))
+    ));
+
     let dns = DnsMessageBuilder::new(header, vec![question]);
     assert_eq!(dns.build_messages().unwrap(), vec![b"\x00\x01\x01\x00\x00\x01\x00\x00\x00\x00\x00\x00\x07\x65\x78\x61\x6d\x70\x6c\x65\x03\x63\x6f\x6d\x00\x00\x01\x00\x01"])
 }
@@ -40,10 +70,565 @@ fn test_response() {
         0, 0, 28, 0, 1, 192, 12, 0, 28, 0, 1, 0, 0, 1, 19, 0, 16, 42, 0, 20, 80, 64, 1, 8, 32, 0,
         0, 0, 0, 0, 0, 32, 14,
     ];
-    let ips = vec![Ip::new(
-        String::from("2a00:1450:4001:820::200e"),
+    let ips = vec![record(
+        "google.com",
         Qtype::AAAA,
+        275,
+        RData::AAAA("2a00:1450:4001:820::200e".parse().unwrap()),
+    )];
+    let hardresult = Response::new("google.com".parse().unwrap(), ips);
+    let response = Response::parse_response(&response).unwrap();
+    assert_eq!(hardresult, response);
+    assert_eq!(
+        response.ips(),
+        vec!["2a00:1450:4001:820::200e"
+            .parse::<std::net::IpAddr>()
+            .unwrap()]
+    );
+    assert_eq!(response.records(Qtype::AAAA).len(), 1);
+    assert!(response.records(Qtype::A).is_empty());
+}
+
+#[test]
+fn test_reverse_response() {
+    let ip = "8.8.8.8".parse().unwrap();
+    let question = Question::reverse(&ip);
+    assert_eq!(question.name.to_string(), "8.8.8.8.in-addr.arpa");
+
+    let response: Vec<u8> = vec![
+        0, 2, 129, 128, 0, 1, 0, 1, 0, 0, 0, 0, 1, 56, 1, 56, 1, 56, 1, 56, 7, 105, 110, 45, 97,
+        100, 100, 114, 4, 97, 114, 112, 97, 0, 0, 12, 0, 1, 192, 12, 0, 12, 0, 1, 0, 0, 84, 96, 0,
+        12, 3, 100, 110, 115, 6, 103, 111, 111, 103, 108, 101, 0,
+    ];
+    let ptrs = vec![record(
+        "8.8.8.8.in-addr.arpa",
+        Qtype::PTR,
+        21600,
+        RData::PTR("dns.google".parse().unwrap()),
+    )];
+    let hardresult = Response::new("8.8.8.8.in-addr.arpa".parse().unwrap(), ptrs);
+    assert_eq!(hardresult, Response::parse_response(&response).unwrap());
+}
+
+#[test]
+fn test_resolv_conf_from_path() {
+    let path = std::env::temp_dir().join(format!("nslookup-resolv-{}.conf", std::process::id()));
+    std::fs::write(
+        &path,
+        "nameserver 192.0.2.53\nsearch example.com\noptions ndots:3 attempts:1\n",
+    )
+    .unwrap();
+    let config = ResolverConfig::from_resolv_conf(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        config.server_addrs(),
+        vec!["192.0.2.53:53".parse().unwrap()]
+    );
+    assert_eq!(
+        config.candidate_names("www.foo"),
+        vec!["www.foo.example.com", "www.foo"]
+    );
+    assert_eq!(config.attempts, 1);
+    assert!(ResolverConfig::from_resolv_conf("/nonexistent/resolv.conf").is_err());
+}
+
+#[test]
+fn test_mx_response() {
+    let response: Vec<u8> = vec![
+        0, 2, 129, 128, 0, 1, 0, 2, 0, 0, 0, 0, 5, 103, 109, 97, 105, 108, 3, 99, 111, 109, 0, 0,
+        15, 0, 1, 192, 12, 0, 15, 0, 1, 0, 0, 1, 44, 0, 30, 0, 20, 13, 103, 109, 97, 105, 108, 45,
+        115, 109, 116, 112, 45, 105, 110, 1, 108, 6, 103, 111, 111, 103, 108, 101, 3, 99, 111, 109,
+        0, 192, 12, 0, 15, 0, 1, 0, 0, 1, 44, 0, 9, 0, 5, 4, 97, 108, 116, 49, 192, 41,
+    ];
+    let mxs = vec![
+        record(
+            "gmail.com",
+            Qtype::MX,
+            300,
+            RData::MX {
+                preference: 20,
+                exchange: "gmail-smtp-in.l.google.com".parse().unwrap(),
+            },
+        ),
+        record(
+            "gmail.com",
+            Qtype::MX,
+            300,
+            RData::MX {
+                preference: 5,
+                exchange: "alt1.gmail-smtp-in.l.google.com".parse().unwrap(),
+            },
+        ),
+    ];
+    let hardresult = Response::new("gmail.com".parse().unwrap(), mxs);
+    let response = Response::parse_response(&response).unwrap();
+    assert_eq!(hardresult, response);
+    assert_eq!(
+        response.to_string(),
+        "Domain: gmail.com\nAdress(es):\nMX: 5 alt1.gmail-smtp-in.l.google.com (ttl 300)\nMX: 20 gmail-smtp-in.l.google.com (ttl 300)\n"
+    );
+}
+
+#[test]
+fn test_txt_response() {
+    let response: Vec<u8> = vec![
+        0, 2, 129, 128, 0, 1, 0, 1, 0, 0, 0, 0, 7, 101, 120, 97, 109, 112, 108, 101, 3, 99, 111,
+        109, 0, 0, 16, 0, 1, 192, 12, 0, 16, 0, 1, 0, 0, 1, 44, 0, 16, 11, 118, 61, 115, 112, 102,
+        49, 32, 45, 97, 108, 108, 3, 97, 7, 34,
+    ];
+    let txts = vec![record(
+        "example.com",
+        Qtype::TXT,
+        300,
+        RData::TXT(vec![b"v=spf1 -all".to_vec(), b"a\x07\"".to_vec()]),
     )];
-    let hardresult = Response::new(String::from("google.com"), ips);
-    assert_eq!(hardresult, Response::parse_response(&response, 28).unwrap());
+    let hardresult = Response::new("example.com".parse().unwrap(), txts);
+    let response = Response::parse_response(&response).unwrap();
+    assert_eq!(hardresult, response);
+    assert_eq!(
+        response.answers[0].rdata.to_string(),
+        "\"v=spf1 -all\" \"a\\007\\\"\""
+    );
+}
+
+#[test]
+fn test_ns_response() {
+    let response: Vec<u8> = vec![
+        0, 2, 129, 128, 0, 1, 0, 2, 0, 0, 0, 0, 6, 103, 111, 111, 103, 108, 101, 3, 99, 111, 109,
+        0, 0, 2, 0, 1, 192, 12, 0, 2, 0, 1, 0, 0, 1, 44, 0, 6, 3, 110, 115, 49, 192, 12, 192, 12,
+        0, 2, 0, 1, 0, 0, 1, 44, 0, 6, 3, 110, 115, 50, 192, 12,
+    ];
+    let ns = vec![
+        record(
+            "google.com",
+            Qtype::NS,
+            300,
+            RData::NS("ns1.google.com".parse().unwrap()),
+        ),
+        record(
+            "google.com",
+            Qtype::NS,
+            300,
+            RData::NS("ns2.google.com".parse().unwrap()),
+        ),
+    ];
+    let hardresult = Response::new("google.com".parse().unwrap(), ns);
+    assert_eq!(hardresult, Response::parse_response(&response).unwrap());
+}
+
+#[test]
+fn test_soa_in_authority() {
+    let response: Vec<u8> = vec![
+        0, 2, 129, 128, 0, 1, 0, 0, 0, 1, 0, 0, 3, 119, 119, 119, 6, 103, 111, 111, 103, 108, 101,
+        3, 99, 111, 109, 0, 0, 28, 0, 1, 192, 16, 0, 6, 0, 1, 0, 0, 1, 44, 0, 38, 3, 110, 115, 49,
+        192, 16, 9, 100, 110, 115, 45, 97, 100, 109, 105, 110, 192, 16, 36, 215, 169, 246, 0, 0, 3,
+        132, 0, 0, 3, 132, 0, 0, 7, 8, 0, 0, 0, 60,
+    ];
+    let response = Response::parse_response(&response).unwrap();
+    assert_eq!(response.domain.to_string(), "www.google.com");
+    assert!(response.answers.is_empty());
+    assert!(response.is_nodata());
+    assert_eq!(
+        response.authority,
+        vec![record(
+            "google.com",
+            Qtype::SOA,
+            300,
+            RData::SOA {
+                mname: "ns1.google.com".parse().unwrap(),
+                rname: "dns-admin.google.com".parse().unwrap(),
+                serial: 618113526,
+                refresh: 900,
+                retry: 900,
+                expire: 1800,
+                minimum: 60,
+            }
+        )]
+    );
+    assert_eq!(
+        response.to_string(),
+        "Domain: www.google.com\nAdress(es):\nAuthority:\nSOA: ns1.google.com dns-admin.google.com 618113526 900 900 1800 60 (ttl 300)\n"
+    );
+}
+
+#[test]
+fn test_lookup_srv() {
+    let mut response: Vec<u8> = vec![
+        0, 2, 129, 128, 0, 1, 0, 2, 0, 0, 0, 0, 4, 95, 115, 105, 112, 4, 95, 116, 99, 112, 7, 101,
+        120, 97, 109, 112, 108, 101, 3, 99, 111, 109, 0, 0, 33, 0, 1, 192, 12, 0, 33, 0, 1, 0, 0,
+        1, 44, 0, 13, 0, 20, 0, 0, 19, 196, 4, 115, 105, 112, 50, 192, 22, 192, 12, 0, 33, 0, 1, 0,
+        0, 1, 44, 0, 13, 0, 10, 0, 60, 19, 196, 4, 115, 105, 112, 49, 192, 22,
+    ];
+    let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap();
+    let handle = thread::spawn(move || {
+        let mut buf = [0u8; 512];
+        let (_, src) = server.recv_from(&mut buf).unwrap();
+        // the reply has to carry the random ID of the query
+        response[..2].copy_from_slice(&buf[..2]);
+        server.send_to(&response, src).unwrap();
+    });
+
+    let mut config = ResolverConfig::new(addr.ip());
+    config.port = addr.port();
+    let targets = lookup_srv("_sip._tcp.example.com", &config).unwrap();
+    handle.join().unwrap();
+    assert_eq!(
+        targets,
+        vec![
+            SrvTarget::new(10, 60, 5060, "sip1.example.com"),
+            SrvTarget::new(20, 0, 5060, "sip2.example.com"),
+        ]
+    );
+}
+
+#[test]
+fn test_unknown_type_response() {
+    let question = Question::new("example.com", "TYPE65534".parse().unwrap()).unwrap();
+    assert_eq!(
+        question.get_question(),
+        b"\x07\x65\x78\x61\x6d\x70\x6c\x65\x03\x63\x6f\x6d\x00\xff\xfe\x00\x01"
+    );
+
+    let response: Vec<u8> = vec![
+        0, 2, 129, 128, 0, 1, 0, 1, 0, 0, 0, 0, 7, 101, 120, 97, 109, 112, 108, 101, 3, 99, 111,
+        109, 0, 255, 254, 0, 1, 192, 12, 255, 254, 0, 1, 0, 0, 1, 44, 0, 3, 10, 11, 12,
+    ];
+    let response = Response::parse_response(&response).unwrap();
+    assert_eq!(
+        response.answers,
+        vec![record(
+            "example.com",
+            Qtype::Unknown(65534),
+            300,
+            RData::Unknown(vec![10, 11, 12])
+        )]
+    );
+    assert_eq!(
+        response.to_string(),
+        "Domain: example.com\nAdress(es):\nTYPE65534: \\# 3 0a0b0c (ttl 300)\n"
+    );
+}
+
+#[test]
+fn test_message_sections() {
+    let response: Vec<u8> = vec![
+        18, 52, 133, 128, 0, 1, 0, 1, 0, 0, 0, 1, 7, 101, 120, 97, 109, 112, 108, 101, 3, 99, 111,
+        109, 0, 0, 15, 0, 1, 192, 12, 0, 15, 0, 1, 0, 0, 14, 16, 0, 9, 0, 10, 4, 109, 97, 105, 108,
+        192, 12, 192, 43, 0, 1, 0, 1, 0, 0, 14, 16, 0, 4, 192, 0, 2, 1,
+    ];
+    let message = Message::parse(&response).unwrap();
+    assert_eq!(message.header.id, 0x1234);
+    assert!(message.header.qr && message.header.aa && message.header.rd && message.header.ra);
+    assert!(!message.header.tc);
+    assert_eq!(message.header.arcount, 1);
+    assert_eq!(
+        message.header.to_string(),
+        ";; opcode: QUERY, status: NOERROR, id: 4660\n;; flags: qr aa rd ra; QUERY: 1, ANSWER: 1, AUTHORITY: 0, ADDITIONAL: 1"
+    );
+    assert_eq!(
+        message.questions,
+        vec![Question::new("example.com", Qtype::MX).unwrap()]
+    );
+    assert_eq!(
+        message.answers,
+        vec![record(
+            "example.com",
+            Qtype::MX,
+            3600,
+            RData::MX {
+                preference: 10,
+                exchange: "mail.example.com".parse().unwrap()
+            }
+        )]
+    );
+    assert!(message.authority.is_empty());
+    assert_eq!(
+        message.additional,
+        vec![record(
+            "mail.example.com",
+            Qtype::A,
+            3600,
+            RData::A("192.0.2.1".parse().unwrap())
+        )]
+    );
+
+    // the glue record is not part of the answer
+    let response = Response::parse_response(&response).unwrap();
+    assert_eq!(response.answers.len(), 1);
+}
+
+#[test]
+fn test_fuzz_corpus() {
+    // the regression corpus of the fuzz target, only the valid_ packages may parse
+    let corpus = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus/parse_message");
+    let mut entries = std::fs::read_dir(corpus)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    entries.sort();
+    assert!(!entries.is_empty());
+    for path in entries {
+        let data = std::fs::read(&path).unwrap();
+        let valid = path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("valid_");
+        if valid {
+            assert!(Message::parse(&data).is_ok(), "{}", path.display());
+            assert!(
+                Response::parse_response(&data).is_ok(),
+                "{}",
+                path.display()
+            );
+        } else {
+            // every invalid package has to be reported as malformed, not e.g. as an empty response
+            assert!(
+                matches!(Message::parse(&data), Err(CustomError::Malformed(..))),
+                "{}",
+                path.display()
+            );
+            assert!(
+                matches!(
+                    Response::parse_response(&data),
+                    Err(CustomError::Malformed(..))
+                ),
+                "{}",
+                path.display()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_chaos_txt_response() {
+    let question = Question::new("version.bind", Qtype::TXT)
+        .unwrap()
+        .with_class(Class::CH);
+    assert_eq!(
+        question.get_question(),
+        b"\x07version\x04bind\x00\x00\x10\x00\x03"
+    );
+
+    let response: Vec<u8> = vec![
+        0, 2, 132, 0, 0, 1, 0, 1, 0, 0, 0, 0, 7, 118, 101, 114, 115, 105, 111, 110, 4, 98, 105,
+        110, 100, 0, 0, 16, 0, 3, 192, 12, 0, 16, 0, 3, 0, 0, 14, 136, 0, 7, 6, 57, 46, 49, 56, 46,
+        49,
+    ];
+    let response = Response::parse_response(&response).unwrap();
+    assert_eq!(
+        response.answers,
+        vec![Record::new(
+            "version.bind".parse().unwrap(),
+            Qtype::TXT,
+            Class::CH,
+            3720,
+            RData::TXT(vec![b"9.18.1".to_vec()])
+        )]
+    );
+    assert_eq!(
+        response.format(true),
+        "Domain: version.bind\nAdress(es):\nTXT CH: \"9.18.1\" (ttl 1h2m)\n"
+    );
+}
+
+#[test]
+fn test_nxdomain_response() {
+    // NXDOMAIN with the SOA of the parent zone in the authority section
+    let response: Vec<u8> = vec![
+        0, 2, 129, 131, 0, 1, 0, 0, 0, 1, 0, 0, 3, 102, 111, 111, 7, 101, 120, 97, 109, 112, 108,
+        101, 0, 0, 1, 0, 1, 0, 0, 6, 0, 1, 0, 0, 3, 132, 0, 64, 1, 97, 12, 114, 111, 111, 116, 45,
+        115, 101, 114, 118, 101, 114, 115, 3, 110, 101, 116, 0, 5, 110, 115, 116, 108, 100, 12,
+        118, 101, 114, 105, 115, 105, 103, 110, 45, 103, 114, 115, 3, 99, 111, 109, 0, 120, 164,
+        230, 52, 0, 0, 7, 8, 0, 0, 3, 132, 0, 9, 58, 128, 0, 1, 81, 128,
+    ];
+    let parsed = Response::parse_response(&response).unwrap();
+    assert_eq!(parsed.rcode, Rcode::NXDomain);
+    assert_eq!(parsed.domain.to_string(), "foo.example");
+    assert!(parsed.answers.is_empty());
+    assert_eq!(parsed.authority.len(), 1);
+    assert!(matches!(
+        parsed.check_rcode(),
+        Err(CustomError::Rcode(Rcode::NXDomain))
+    ));
+
+    // SERVFAIL without any record
+    let mut servfail = response[..29].to_vec();
+    servfail[3] = 130;
+    servfail[9] = 0;
+    let parsed = Response::parse_response(&servfail).unwrap();
+    assert_eq!(parsed.rcode, Rcode::ServFail);
+
+    // BADCOOKIE needs the extended rcode of the OPT record
+    let mut badcookie = servfail.clone();
+    badcookie[3] = 135;
+    badcookie[11] = 1;
+    badcookie.extend_from_slice(&[0, 0, 41, 16, 0, 1, 0, 0, 0, 0, 0]);
+    assert_eq!(
+        Response::parse_response(&badcookie).unwrap().rcode,
+        Rcode::BadCookie
+    );
+}
+
+#[test]
+fn test_edns_message() {
+    let header = Header::new(0x1234, false, 0);
+    let edns = Edns::new(4096).with_do(true).with_option(3, vec![]);
+    let dns = DnsMessageBuilder::new(
+        header,
+        vec![Question::new("example.com", Qtype::A).unwrap()],
+    )
+    .with_edns(edns.clone());
+    let message = dns.build_messages().unwrap().remove(0);
+    assert_eq!(&message[10..12], &[0, 1]);
+    assert_eq!(
+        &message[29..],
+        &[0, 0, 41, 16, 0, 0, 0, 128, 0, 0, 4, 0, 3, 0, 0]
+    );
+
+    // the query itself parses like a reply, the OPT record isn't part of the additional section
+    let parsed = Message::parse(&message).unwrap();
+    assert!(parsed.additional.is_empty());
+    assert_eq!(parsed.edns, Some(edns));
+    assert_eq!(
+        parsed.edns.unwrap().to_string(),
+        ";; OPT PSEUDOSECTION:\n; EDNS: version: 0, flags: do; udp: 4096\n; NSID: "
+    );
+
+    // an option that is longer than the RDATA, the offset of the error is its length field in the package
+    let mut malformed = message.clone();
+    malformed[43] = 1;
+    assert!(matches!(
+        Message::parse(&malformed),
+        Err(CustomError::Malformed(42, ParseReason::RdataLength))
+    ));
+}
+
+#[test]
+fn test_client_subnet_response() {
+    let subnet = "203.0.113.0/24".parse::<ClientSubnet>().unwrap();
+    let dns = DnsMessageBuilder::new(
+        Header::new(0x1234, false, 0),
+        vec![Question::new("example.com", Qtype::A).unwrap()],
+    )
+    .with_edns(Edns::new(1232).with_client_subnet(&subnet));
+    assert_eq!(
+        dns.build_messages().unwrap()[0][29..],
+        [0, 0, 41, 4, 208, 0, 0, 0, 0, 0, 11, 0, 8, 0, 7, 0, 1, 24, 0, 203, 0, 113]
+    );
+
+    // the server answers for the whole /20
+    let response: Vec<u8> = vec![
+        18, 52, 129, 128, 0, 1, 0, 1, 0, 0, 0, 1, 7, 101, 120, 97, 109, 112, 108, 101, 3, 99, 111,
+        109, 0, 0, 1, 0, 1, 192, 12, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 192, 0, 2, 1, 0, 0, 41, 4, 208,
+        0, 0, 0, 0, 0, 11, 0, 8, 0, 7, 0, 1, 24, 20, 203, 0, 113,
+    ];
+    let message = Message::parse(&response).unwrap();
+    let edns = message.edns.unwrap();
+    let reply = edns.client_subnet().unwrap();
+    assert_eq!(reply.address, subnet.address);
+    assert_eq!((reply.source_prefix, reply.scope_prefix), (24, 20));
+    assert_eq!(
+        edns.to_string(),
+        ";; OPT PSEUDOSECTION:\n; EDNS: version: 0, flags:; udp: 1232\n; CLIENT-SUBNET: 203.0.113.0/24/20"
+    );
+    assert_eq!(Response::parse_response(&response).unwrap().ips().len(), 1);
+}
+
+#[test]
+fn test_combined_response() {
+    let cname = [192, 12, 0, 5, 0, 1, 0, 0, 0, 60, 0, 2, 192, 16];
+    let question = |qtype: u8| {
+        vec![
+            3, 119, 119, 119, 7, 101, 120, 97, 109, 112, 108, 101, 3, 99, 111, 109, 0, 0, qtype, 0,
+            1,
+        ]
+    };
+    let a = [
+        &[0, 1, 129, 128, 0, 1, 0, 2, 0, 0, 0, 0][..],
+        &question(1),
+        &cname,
+        &[192, 16, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 192, 0, 2, 1],
+    ]
+    .concat();
+    let aaaa = [
+        &[0, 2, 129, 128, 0, 1, 0, 2, 0, 0, 0, 0][..],
+        &question(28),
+        &cname,
+        &[192, 16, 0, 28, 0, 1, 0, 0, 0, 60, 0, 16],
+        &[32, 1, 13, 184, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+    ]
+    .concat();
+
+    // the CNAME is in both replies but only once in the Response
+    let response = Response::from_replies(&[a.clone(), aaaa.clone()]).unwrap();
+    assert_eq!(response.domain.to_string(), "www.example.com");
+    assert_eq!(
+        response.answers,
+        vec![
+            record(
+                "www.example.com",
+                Qtype::CNAME,
+                60,
+                RData::CNAME("example.com".parse().unwrap())
+            ),
+            record(
+                "example.com",
+                Qtype::A,
+                60,
+                RData::A("192.0.2.1".parse().unwrap())
+            ),
+            record(
+                "example.com",
+                Qtype::AAAA,
+                60,
+                RData::AAAA("2001:db8::1".parse().unwrap())
+            ),
+        ]
+    );
+
+    // a failed question doesn't hide the answer of the other one
+    let mut servfail = aaaa[..33].to_vec();
+    servfail[3] = 130;
+    servfail[7] = 0;
+    assert_eq!(
+        Response::from_replies(&[a, servfail.clone()])
+            .unwrap()
+            .ips()
+            .len(),
+        1
+    );
+    let mut empty = servfail.clone();
+    empty[3] = 128;
+    assert!(matches!(
+        Response::from_replies(&[empty.clone(), servfail]),
+        Err(CustomError::Rcode(Rcode::ServFail))
+    ));
+    assert!(matches!(
+        Response::from_replies(&[empty]),
+        Err(CustomError::EmptyResponse)
+    ));
+}
+
+#[test]
+fn test_idna_response() {
+    let question = Question::new("bücher.de", Qtype::A).unwrap();
+    assert_eq!(
+        question.get_question(),
+        b"\x0dxn--bcher-kva\x02de\x00\x00\x01\x00\x01"
+    );
+
+    let response: Vec<u8> = vec![
+        0, 2, 129, 128, 0, 1, 0, 1, 0, 0, 0, 0, 13, 120, 110, 45, 45, 98, 99, 104, 101, 114, 45,
+        107, 118, 97, 2, 100, 101, 0, 0, 1, 0, 1, 192, 12, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 192, 0,
+        2, 7,
+    ];
+    let response = Response::parse_response(&response).unwrap();
+    assert_eq!(response.domain.to_string(), "xn--bcher-kva.de");
+    assert_eq!(
+        response.to_string(),
+        "Domain: bücher.de (xn--bcher-kva.de)\nAdress(es):\nIPv4: 192.0.2.7 (ttl 60)\n"
+    );
 }